@@ -1,71 +1,162 @@
 //! A module for the abstract syntax tree (AST) that many tools will use.
 
 mod macros;
+mod printer;
+mod typed;
+
+use crate::lexer::{Span, Spanned};
+use crate::number::Integer;
+use std::collections::BTreeSet;
+use std::fmt::{Display, Formatter};
+
+pub(crate) use crate::ast::printer::{write_annotations, write_arg_list};
+pub use crate::ast::typed::{AExp, BExp, Stmt};
 
 /// A recursive type representing the AST. The AST itself stores no
 /// functionality or logic on how each node behaves -- that is the purview
 /// of other tools that use the AST, like the interpreter.
+///
+/// Every child node is a `Spanned<Ast>`, so that each part of the tree knows
+/// where it came from in the source. Nodes that the parser lowers, like the
+/// `!(b <= a)` that `a < b` becomes, have the span of the source they were
+/// lowered from.
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub enum Ast {
     // Literals and identifiers
     True,
     False,
-    Literal(i32),
+    Literal(Integer),
     Ident(String),
 
     // Comparison and equality
+    /// A negation. The parser lowers some operators to a negation, and records
+    /// which in `sugar`, so that they can be printed the way they were written.
     Not {
-        expr: Box<Self>,
+        expr: Box<Spanned<Ast>>,
+        sugar: Option<Sugar>,
     },
     Eq {
-        left: Box<Self>,
-        right: Box<Self>,
+        left: Box<Spanned<Ast>>,
+        right: Box<Spanned<Ast>>,
     },
     LessEq {
-        left: Box<Self>,
-        right: Box<Self>,
+        left: Box<Spanned<Ast>>,
+        right: Box<Spanned<Ast>>,
     },
     And {
-        left: Box<Self>,
-        right: Box<Self>,
+        left: Box<Spanned<Ast>>,
+        right: Box<Spanned<Ast>>,
     },
 
     // Arithmetic
     Add {
-        left: Box<Self>,
-        right: Box<Self>,
+        left: Box<Spanned<Ast>>,
+        right: Box<Spanned<Ast>>,
     },
     Sub {
-        left: Box<Self>,
-        right: Box<Self>,
+        left: Box<Spanned<Ast>>,
+        right: Box<Spanned<Ast>>,
     },
     Mul {
-        left: Box<Self>,
-        right: Box<Self>,
+        left: Box<Spanned<Ast>>,
+        right: Box<Spanned<Ast>>,
+    },
+    Div {
+        left: Box<Spanned<Ast>>,
+        right: Box<Spanned<Ast>>,
+    },
+    Mod {
+        left: Box<Spanned<Ast>>,
+        right: Box<Spanned<Ast>>,
     },
 
     // Statements
     Comp {
-        first: Box<Self>,
-        second: Box<Self>,
+        first: Box<Spanned<Ast>>,
+        second: Box<Spanned<Ast>>,
     },
     Ass {
         ident: String,
-        value: Box<Self>,
+        value: Box<Spanned<Ast>>,
     },
     Skip,
     If {
-        cond: Box<Self>,
-        true_path: Box<Self>,
-        false_path: Box<Self>,
+        cond: Box<Spanned<Ast>>,
+        true_path: Box<Spanned<Ast>>,
+        false_path: Box<Spanned<Ast>>,
     },
+    /// A loop, like `while b inv I var e do S`, with an optional invariant
+    /// `I`, which is a boolean formula, and an optional variant `e`, which is
+    /// an arithmetic expression.
     While {
-        cond: Box<Self>,
-        body: Box<Self>,
+        cond: Box<Spanned<Ast>>,
+        body: Box<Spanned<Ast>>,
+        invariant: Option<Box<Spanned<Ast>>>,
+        variant: Option<Box<Spanned<Ast>>>,
     },
     DefinitionRun {
         ident: String,
     },
+    /// A parameterised definition, like `Add(a, b; r) := [[r := a + b]]`. The
+    /// inputs come before the `;`, and the outputs after it.
+    Procedure {
+        ident: String,
+        inputs: Vec<String>,
+        outputs: Vec<String>,
+        body: Box<Spanned<Ast>>,
+    },
+    /// A run of a parameterised definition, like `Add(x, 3; z)`. Inputs are
+    /// arithmetic expressions, and outputs are the variables that the results
+    /// are assigned to.
+    Call {
+        ident: String,
+        inputs: Vec<Spanned<Ast>>,
+        outputs: Vec<String>,
+    },
+    /// A block with local variables, like `begin var x := 1; S end`. The
+    /// declarations run in order, and the variables they declare are put back
+    /// to what they were before once the block has finished.
+    Block {
+        declarations: Vec<(String, Spanned<Ast>)>,
+        body: Box<Spanned<Ast>>,
+    },
+    /// `assert b`, which stops the run with an error if `b` doesn't hold.
+    Assert {
+        cond: Box<Spanned<Ast>>,
+    },
+    /// `assume b`, which ends the run as infeasible if `b` doesn't hold.
+    Assume {
+        cond: Box<Spanned<Ast>>,
+    },
+
+    // Assertions
+    /// An assertion, like `{x = x0}`, that the formula holds at this point in
+    /// the program. Assertions are for reasoning about programs, so running
+    /// one does nothing.
+    Assertion {
+        formula: Box<Spanned<Ast>>,
+    },
+    /// A Hoare triple, `{pre} stmt {post}`: if `pre` holds before `stmt` runs,
+    /// then `post` holds after it. `pre` and `post` are the formulas, without
+    /// their braces.
+    Triple {
+        pre: Box<Spanned<Ast>>,
+        stmt: Box<Spanned<Ast>>,
+        post: Box<Spanned<Ast>>,
+    },
+
+    /// A statement that failed to parse, left in place by the parser's error
+    /// recovery.
+    Error,
+}
+
+/// An operator that the parser lowers to the core forms of the `Ast`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Sugar {
+    /// `a | b`, lowered to `!(!a & !b)`.
+    Or,
+    /// `a => b`, lowered to `!(a & !b)`.
+    Implies,
 }
 
 impl Ast {
@@ -76,14 +167,172 @@ impl Ast {
                 | Ast::Comp { .. }
                 | Ast::While { .. }
                 | Ast::DefinitionRun { .. }
+                | Ast::Procedure { .. }
+                | Ast::Call { .. }
+                | Ast::Block { .. }
                 | Ast::Skip
                 | Ast::If { .. }
+                | Ast::Assert { .. }
+                | Ast::Assume { .. }
+                | Ast::Assertion { .. }
+                | Ast::Triple { .. }
         )
     }
+
+    /// The logical variables of a program, like the `x0` of `{x = x0}`: the
+    /// variables that appear in its assertions, but not in its code. They
+    /// stand for values that the assertions talk about, and aren't changed by
+    /// the program.
+    pub fn logical_variables(&self) -> BTreeSet<String> {
+        let mut in_assertions = BTreeSet::new();
+        let mut in_code = BTreeSet::new();
+        self.collect_variables(&mut in_code, &mut in_assertions);
+
+        in_assertions.difference(&in_code).cloned().collect()
+    }
+
+    /// Collects the variables of the tree into `variables`, apart from those
+    /// in assertions, which go into `in_assertions`.
+    fn collect_variables(
+        &self,
+        variables: &mut BTreeSet<String>,
+        in_assertions: &mut BTreeSet<String>,
+    ) {
+        match self {
+            Ast::Ident(ident) => {
+                variables.insert(ident.clone());
+            }
+            Ast::Ass { ident, value } => {
+                variables.insert(ident.clone());
+                value.inner.collect_variables(variables, in_assertions);
+            }
+            Ast::Procedure {
+                inputs,
+                outputs,
+                body,
+                ..
+            } => {
+                variables.extend(inputs.iter().chain(outputs).cloned());
+                body.inner.collect_variables(variables, in_assertions);
+            }
+            Ast::Call {
+                inputs, outputs, ..
+            } => {
+                for input in inputs {
+                    input.inner.collect_variables(variables, in_assertions);
+                }
+                variables.extend(outputs.iter().cloned());
+            }
+            Ast::Block { declarations, body } => {
+                for (ident, value) in declarations {
+                    variables.insert(ident.clone());
+                    value.inner.collect_variables(variables, in_assertions);
+                }
+                body.inner.collect_variables(variables, in_assertions);
+            }
+            Ast::Assertion { formula } => {
+                formula
+                    .inner
+                    .collect_variables(in_assertions, &mut BTreeSet::new());
+            }
+            Ast::Triple { pre, stmt, post } => {
+                pre.inner
+                    .collect_variables(in_assertions, &mut BTreeSet::new());
+                stmt.inner.collect_variables(variables, in_assertions);
+                post.inner
+                    .collect_variables(in_assertions, &mut BTreeSet::new());
+            }
+            Ast::Not { expr, .. } | Ast::Assert { cond: expr } | Ast::Assume { cond: expr } => {
+                expr.inner.collect_variables(variables, in_assertions)
+            }
+            Ast::Eq { left, right }
+            | Ast::LessEq { left, right }
+            | Ast::And { left, right }
+            | Ast::Add { left, right }
+            | Ast::Sub { left, right }
+            | Ast::Mul { left, right }
+            | Ast::Div { left, right }
+            | Ast::Mod { left, right }
+            | Ast::Comp {
+                first: left,
+                second: right,
+            } => {
+                left.inner.collect_variables(variables, in_assertions);
+                right.inner.collect_variables(variables, in_assertions);
+            }
+            Ast::If {
+                cond,
+                true_path,
+                false_path,
+            } => {
+                cond.inner.collect_variables(variables, in_assertions);
+                true_path.inner.collect_variables(variables, in_assertions);
+                false_path.inner.collect_variables(variables, in_assertions);
+            }
+            Ast::While {
+                cond,
+                body,
+                invariant,
+                variant,
+            } => {
+                cond.inner.collect_variables(variables, in_assertions);
+                body.inner.collect_variables(variables, in_assertions);
+
+                // Annotations are assertions about the loop.
+                for annotation in invariant.iter().chain(variant) {
+                    annotation
+                        .inner
+                        .collect_variables(in_assertions, &mut BTreeSet::new());
+                }
+            }
+            Ast::True
+            | Ast::False
+            | Ast::Literal(_)
+            | Ast::Skip
+            | Ast::DefinitionRun { .. }
+            | Ast::Error => {}
+        }
+    }
+}
+
+/// Compares two trees, ignoring their spans. This is so that a parsed tree can
+/// be compared against one that was built by hand.
+impl PartialEq for Spanned<Ast> {
+    fn eq(&self, other: &Self) -> bool {
+        self.inner == other.inner
+    }
+}
+
+impl Eq for Spanned<Ast> {}
+
+/// Compares a tree with a span-less one, ignoring the span.
+impl PartialEq<Ast> for Spanned<Ast> {
+    fn eq(&self, other: &Ast) -> bool {
+        self.inner == *other
+    }
+}
+
+/// Gives a node an empty span at the start of the source, for trees that
+/// weren't parsed from any source, like ones built by hand.
+impl From<Ast> for Spanned<Ast> {
+    fn from(ast: Ast) -> Self {
+        Spanned::new(ast, Span::default())
+    }
 }
 
+#[derive(Debug, Clone, Eq, PartialEq)]
 pub enum Value {
-    I32(i32),
+    Int(Integer),
     Bool(bool),
     Unit,
 }
+
+impl Display for Value {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Int(x) => write!(f, "{x}"),
+            Value::Bool(b) => write!(f, "{b}"),
+            Value::Unit => write!(f, "()"),
+        }
+    }
+}
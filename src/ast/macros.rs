@@ -1,11 +1,14 @@
 /// A macro for creating binary nodes for the [`Ast`]. Used to keep other
 /// macros DRYer.
+///
+/// Like the rest of the macros, its operands can be either an `Ast` or a
+/// `Spanned<Ast>`. Operands without spans are given the default span.
 #[macro_export]
 macro_rules! binary_node {
     ($operation: ident, $left: expr, $right: expr ) => {
         Ast::$operation {
-            left: Box::new($left),
-            right: Box::new($right),
+            left: Box::new($crate::lexer::Spanned::<Ast>::from($left)),
+            right: Box::new($crate::lexer::Spanned::<Ast>::from($right)),
         }
     };
 }
@@ -31,6 +34,20 @@ macro_rules! mul {
     };
 }
 
+#[macro_export]
+macro_rules! div {
+    ( $left:expr, $right: expr ) => {
+        binary_node!(Div, $left, $right)
+    };
+}
+
+#[macro_export]
+macro_rules! modulo {
+    ( $left:expr, $right: expr ) => {
+        binary_node!(Mod, $left, $right)
+    };
+}
+
 #[macro_export]
 macro_rules! less_eq {
     ( $left:expr, $right: expr ) => {
@@ -56,7 +73,36 @@ macro_rules! and {
 macro_rules! not {
     ( $expr:expr ) => {
         Ast::Not {
-            expr: Box::new($expr),
+            expr: Box::new($crate::lexer::Spanned::<Ast>::from($expr)),
+            sugar: None,
+        }
+    };
+}
+
+/// Makes `!(!left & !right)`, recorded as `left | right`.
+#[macro_export]
+macro_rules! or {
+    ( $left:expr, $right: expr ) => {
+        Ast::Not {
+            expr: Box::new($crate::lexer::Spanned::<Ast>::from($crate::and!(
+                $crate::not!($left),
+                $crate::not!($right)
+            ))),
+            sugar: Some($crate::ast::Sugar::Or),
+        }
+    };
+}
+
+/// Makes `!(left & !right)`, recorded as `left => right`.
+#[macro_export]
+macro_rules! implies {
+    ( $left:expr, $right: expr ) => {
+        Ast::Not {
+            expr: Box::new($crate::lexer::Spanned::<Ast>::from($crate::and!(
+                $left,
+                $crate::not!($right)
+            ))),
+            sugar: Some($crate::ast::Sugar::Implies),
         }
     };
 }
@@ -66,7 +112,7 @@ macro_rules! ass_stmt {
     ( $ident:expr, $val: expr ) => {
         Ast::Ass {
             ident: $ident,
-            value: Box::new($val),
+            value: Box::new($crate::lexer::Spanned::<Ast>::from($val)),
         }
     };
 }
@@ -75,9 +121,9 @@ macro_rules! ass_stmt {
 macro_rules! if_stmt {
     ( $cond:expr, $affirm: expr, $neg: expr ) => {
         Ast::If {
-            cond: Box::new($cond),
-            true_path: Box::new($affirm),
-            false_path: Box::new($neg),
+            cond: Box::new($crate::lexer::Spanned::<Ast>::from($cond)),
+            true_path: Box::new($crate::lexer::Spanned::<Ast>::from($affirm)),
+            false_path: Box::new($crate::lexer::Spanned::<Ast>::from($neg)),
         }
     };
 }
@@ -86,8 +132,10 @@ macro_rules! if_stmt {
 macro_rules! while_stmt {
     ( $cond:expr, $body: expr ) => {
         Ast::While {
-            cond: Box::new($cond),
-            body: Box::new($body),
+            cond: Box::new($crate::lexer::Spanned::<Ast>::from($cond)),
+            body: Box::new($crate::lexer::Spanned::<Ast>::from($body)),
+            invariant: None,
+            variant: None,
         }
     };
 }
@@ -96,8 +144,8 @@ macro_rules! while_stmt {
 macro_rules! comp_stmt {
     ( $left:expr, $right: expr ) => {
         Ast::Comp {
-            first: Box::new($left),
-            second: Box::new($right),
+            first: Box::new($crate::lexer::Spanned::<Ast>::from($left)),
+            second: Box::new($crate::lexer::Spanned::<Ast>::from($right)),
         }
     };
 }
@@ -119,7 +167,7 @@ macro_rules! ident {
 #[macro_export]
 macro_rules! literal {
     ( $x:literal) => {
-        Ast::Literal($x)
+        Ast::Literal($crate::number::Integer::from($x))
     };
 }
 
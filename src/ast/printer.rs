@@ -0,0 +1,267 @@
+use crate::ast::{Ast, Sugar};
+use crate::lexer::Spanned;
+use crate::number::Integer;
+use std::fmt::{Display, Formatter};
+
+/// Writes the tree back out as source code, all on one line.
+///
+/// Brackets are only added where they're needed for the code to parse back
+/// into the same tree, and operators are written in their ASCII forms.
+/// Lowered comparisons are written in their lowered forms, but `|` and `=>`
+/// are written as they were parsed, and so are negated literals.
+impl Display for Ast {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Ast::Comp { first, second } => {
+                write_followed(f, first)?;
+                write!(f, "; {}", second.inner)
+            }
+            Ast::Ass { ident, value } if value.inner.is_statement() => {
+                write!(f, "{ident} := [[{}]]", value.inner)
+            }
+            Ast::Ass { ident, value } => write!(f, "{ident} := {}", value.inner),
+            Ast::Skip => write!(f, "skip"),
+            Ast::If {
+                cond,
+                true_path,
+                false_path,
+            } => write!(
+                f,
+                "if {} then {} else {}",
+                cond.inner, true_path.inner, false_path.inner
+            ),
+            Ast::While {
+                cond,
+                body,
+                invariant,
+                variant,
+            } => {
+                write!(f, "while {}", cond.inner)?;
+                write_annotations(f, invariant.as_deref(), variant.as_deref())?;
+                write!(f, " do {}", body.inner)
+            }
+            Ast::DefinitionRun { ident } => write!(f, "{ident}"),
+            Ast::Procedure {
+                ident,
+                inputs,
+                outputs,
+                body,
+            } => {
+                write!(f, "{ident}")?;
+                write_arg_list(f, inputs, outputs)?;
+                write!(f, " := [[{}]]", body.inner)
+            }
+            Ast::Call {
+                ident,
+                inputs,
+                outputs,
+            } => {
+                write!(f, "{ident}")?;
+                write_arg_list(f, inputs.iter().map(|input| &input.inner), outputs)
+            }
+            Ast::Block { declarations, body } => {
+                write!(f, "begin ")?;
+
+                for (ident, value) in declarations {
+                    write!(f, "var {ident} := {}; ", value.inner)?;
+                }
+
+                write!(f, "{} end", body.inner)
+            }
+            Ast::Assert { cond } => write!(f, "assert {}", cond.inner),
+            Ast::Assume { cond } => write!(f, "assume {}", cond.inner),
+            Ast::Assertion { formula } => write!(f, "{{{}}}", formula.inner),
+            Ast::Triple { pre, stmt, post } => {
+                write!(f, "{{{}}} ", pre.inner)?;
+                write_bracketed_block(f, stmt)?;
+                write!(f, " {{{}}}", post.inner)
+            }
+            Ast::Error => write!(f, "<error>"),
+
+            Ast::True => write!(f, "true"),
+            Ast::False => write!(f, "false"),
+            Ast::Literal(value) => write!(f, "{value}"),
+            Ast::Ident(ident) => write!(f, "{ident}"),
+            Ast::Not { expr, .. } => match desugar(self) {
+                Some((Sugar::Or, left, right)) => write_binary(f, self, left, "|", right),
+                Some((Sugar::Implies, left, right)) => write_binary(f, self, left, "=>", right),
+                None => {
+                    write!(f, "!")?;
+                    write_operand(f, expr, precedence(&expr.inner) < precedence(self))
+                }
+            },
+            Ast::And { left, right } => write_binary(f, self, left, "&", right),
+            Ast::Eq { left, right } => write_binary(f, self, left, "=", right),
+            Ast::LessEq { left, right } => write_binary(f, self, left, "<=", right),
+            Ast::Add { left, right } => write_binary(f, self, left, "+", right),
+            Ast::Sub { right, .. } if is_negated_literal(self) => write!(f, "-{}", right.inner),
+            Ast::Sub { left, right } => write_binary(f, self, left, "-", right),
+            Ast::Mul { left, right } => write_binary(f, self, left, "*", right),
+            Ast::Div { left, right } => write_binary(f, self, left, "/", right),
+            Ast::Mod { left, right } => write_binary(f, self, left, "%", right),
+        }
+    }
+}
+
+/// Writes a statement that has another after it. The last block of an `if`
+/// or `while` takes every statement after it, so `(while b do S); T` needs its
+/// brackets.
+fn write_followed(f: &mut Formatter<'_>, stmt: &Spanned<Ast>) -> std::fmt::Result {
+    match &stmt.inner {
+        Ast::If { .. } | Ast::While { .. } => write!(f, "({})", stmt.inner),
+        Ast::Comp { first, second } => {
+            write_followed(f, first)?;
+            write!(f, "; ")?;
+            write_followed(f, second)
+        }
+        _ => write!(f, "{}", stmt.inner),
+    }
+}
+
+/// Writes the invariant and variant of a `while` loop, with a space before
+/// each.
+pub(crate) fn write_annotations(
+    f: &mut impl std::fmt::Write,
+    invariant: Option<&Spanned<Ast>>,
+    variant: Option<&Spanned<Ast>>,
+) -> std::fmt::Result {
+    if let Some(invariant) = invariant {
+        write!(f, " inv {}", invariant.inner)?;
+    }
+
+    if let Some(variant) = variant {
+        write!(f, " var {}", variant.inner)?;
+    }
+
+    Ok(())
+}
+
+/// Writes the arguments of a call, or the parameters of a definition, like
+/// `(x, 3; z)`. The `;` is left out when there are no outputs.
+pub(crate) fn write_arg_list<T: Display>(
+    f: &mut impl std::fmt::Write,
+    inputs: impl IntoIterator<Item = T>,
+    outputs: &[String],
+) -> std::fmt::Result {
+    let inputs: Vec<_> = inputs.into_iter().map(|input| input.to_string()).collect();
+    write!(f, "({}", inputs.join(", "))?;
+
+    if !outputs.is_empty() {
+        write!(f, "; {}", outputs.join(", "))?;
+    }
+
+    write!(f, ")")
+}
+
+/// Writes a statement that has an assertion after it, which an `if` or
+/// `while` would take as part of its last block, as would the last statement
+/// of a composition.
+fn write_bracketed_block(f: &mut Formatter<'_>, stmt: &Spanned<Ast>) -> std::fmt::Result {
+    match &stmt.inner {
+        Ast::If { .. } | Ast::While { .. } | Ast::Comp { .. } => write!(f, "({})", stmt.inner),
+        _ => write!(f, "{}", stmt.inner),
+    }
+}
+
+/// The operands of a negation that was lowered from `|` or `=>`. Returns
+/// `None` for other negations, and for ones whose sugar doesn't match their
+/// shape, which are written as plain negations.
+fn desugar(ast: &Ast) -> Option<(Sugar, &Spanned<Ast>, &Spanned<Ast>)> {
+    let Ast::Not {
+        expr,
+        sugar: Some(sugar),
+    } = ast
+    else {
+        return None;
+    };
+
+    let Ast::And { left, right } = &expr.inner else {
+        return None;
+    };
+
+    match (sugar, &left.inner, &right.inner) {
+        (Sugar::Or, Ast::Not { expr: left, .. }, Ast::Not { expr: right, .. }) => {
+            Some((Sugar::Or, left, right))
+        }
+        (Sugar::Implies, _, Ast::Not { expr: right, .. }) => Some((Sugar::Implies, left, right)),
+        _ => None,
+    }
+}
+
+/// Whether an expression is the `0 - N` that a negated literal `-N` is
+/// lowered to. It's written back as `-N`, since the literal might only be in
+/// range once it's negated. A `0 - N` written out in full is written the same
+/// way, which parses back to the same tree.
+fn is_negated_literal(ast: &Ast) -> bool {
+    match ast {
+        Ast::Sub { left, right } => {
+            left.inner == Ast::Literal(Integer::zero()) && matches!(right.inner, Ast::Literal(_))
+        }
+        _ => false,
+    }
+}
+
+/// How tightly an expression binds, following the grammar. Higher binds
+/// tighter.
+fn precedence(ast: &Ast) -> u8 {
+    match desugar(ast) {
+        Some((Sugar::Implies, ..)) => return 1,
+        Some((Sugar::Or, ..)) => return 2,
+        None => {}
+    }
+
+    if is_negated_literal(ast) {
+        return 8;
+    }
+
+    match ast {
+        Ast::And { .. } => 3,
+        Ast::Eq { .. } => 4,
+        Ast::LessEq { .. } => 5,
+        Ast::Add { .. } | Ast::Sub { .. } => 6,
+        Ast::Mul { .. } | Ast::Div { .. } | Ast::Mod { .. } => 7,
+        Ast::Not { .. } => 8,
+        _ => 9,
+    }
+}
+
+/// Writes a binary operation, bracketing the operands that bind more loosely
+/// than it. Operators are left associative, apart from `=>`, and the
+/// comparisons, which don't associate at all.
+fn write_binary(
+    f: &mut Formatter<'_>,
+    ast: &Ast,
+    left: &Spanned<Ast>,
+    operator: &str,
+    right: &Spanned<Ast>,
+) -> std::fmt::Result {
+    let precedence_of = |operand: &Spanned<Ast>| precedence(&operand.inner);
+    let own = precedence(ast);
+
+    let (left_brackets, right_brackets) = match ast {
+        Ast::Eq { .. } | Ast::LessEq { .. } => {
+            (precedence_of(left) <= own, precedence_of(right) <= own)
+        }
+        Ast::Not {
+            sugar: Some(Sugar::Implies),
+            ..
+        } => (precedence_of(left) <= own, precedence_of(right) < own),
+        _ => (precedence_of(left) < own, precedence_of(right) <= own),
+    };
+
+    write_operand(f, left, left_brackets)?;
+    write!(f, " {operator} ")?;
+    write_operand(f, right, right_brackets)
+}
+
+fn write_operand(
+    f: &mut Formatter<'_>,
+    operand: &Spanned<Ast>,
+    brackets: bool,
+) -> std::fmt::Result {
+    if brackets {
+        write!(f, "({})", operand.inner)
+    } else {
+        write!(f, "{}", operand.inner)
+    }
+}
@@ -0,0 +1,355 @@
+use crate::ast::Ast;
+use crate::lexer::{Span, Spanned};
+use crate::number::Integer;
+use crate::parser::{Category, ParseError, ParseErrorKind};
+
+/// A statement of the handbook grammar.
+///
+/// Unlike the [`Ast`], the typed tree can only hold well-formed programs: an
+/// assignment's value is always an [`AExp`], and a condition is always a
+/// [`BExp`]. Make one with [`Stmt::from_ast`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Stmt {
+    Ass {
+        ident: String,
+        value: Box<Spanned<AExp>>,
+    },
+    /// An assignment of a statement to a name, like `W := [[x := 1]]`.
+    Define {
+        ident: String,
+        body: Box<Spanned<Stmt>>,
+    },
+    Skip,
+    Comp {
+        first: Box<Spanned<Stmt>>,
+        second: Box<Spanned<Stmt>>,
+    },
+    If {
+        cond: Box<Spanned<BExp>>,
+        true_path: Box<Spanned<Stmt>>,
+        false_path: Box<Spanned<Stmt>>,
+    },
+    While {
+        cond: Box<Spanned<BExp>>,
+        body: Box<Spanned<Stmt>>,
+        invariant: Option<Box<Spanned<BExp>>>,
+        variant: Option<Box<Spanned<AExp>>>,
+    },
+    DefinitionRun {
+        ident: String,
+    },
+    Procedure {
+        ident: String,
+        inputs: Vec<String>,
+        outputs: Vec<String>,
+        body: Box<Spanned<Stmt>>,
+    },
+    Call {
+        ident: String,
+        inputs: Vec<Spanned<AExp>>,
+        outputs: Vec<String>,
+    },
+    Block {
+        declarations: Vec<(String, Spanned<AExp>)>,
+        body: Box<Spanned<Stmt>>,
+    },
+    Assert {
+        cond: Box<Spanned<BExp>>,
+    },
+    Assume {
+        cond: Box<Spanned<BExp>>,
+    },
+    Assertion {
+        formula: Box<Spanned<BExp>>,
+    },
+    Triple {
+        pre: Box<Spanned<BExp>>,
+        stmt: Box<Spanned<Stmt>>,
+        post: Box<Spanned<BExp>>,
+    },
+}
+
+/// An arithmetic expression of the handbook grammar.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum AExp {
+    Literal(Integer),
+    Ident(String),
+    Add {
+        left: Box<Spanned<AExp>>,
+        right: Box<Spanned<AExp>>,
+    },
+    Sub {
+        left: Box<Spanned<AExp>>,
+        right: Box<Spanned<AExp>>,
+    },
+    Mul {
+        left: Box<Spanned<AExp>>,
+        right: Box<Spanned<AExp>>,
+    },
+    Div {
+        left: Box<Spanned<AExp>>,
+        right: Box<Spanned<AExp>>,
+    },
+    Mod {
+        left: Box<Spanned<AExp>>,
+        right: Box<Spanned<AExp>>,
+    },
+}
+
+/// A boolean expression of the handbook grammar.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum BExp {
+    True,
+    False,
+    Not {
+        expr: Box<Spanned<BExp>>,
+    },
+    And {
+        left: Box<Spanned<BExp>>,
+        right: Box<Spanned<BExp>>,
+    },
+    Eq {
+        left: Box<Spanned<AExp>>,
+        right: Box<Spanned<AExp>>,
+    },
+    /// An equality between two booleans, which the handbook grammar leaves
+    /// out, but the interpreter allows.
+    BoolEq {
+        left: Box<Spanned<BExp>>,
+        right: Box<Spanned<BExp>>,
+    },
+    LessEq {
+        left: Box<Spanned<AExp>>,
+        right: Box<Spanned<AExp>>,
+    },
+}
+
+impl Stmt {
+    /// Converts a statement of the `Ast`, checking that every part of it is of
+    /// the right category. The error points at the first part that isn't.
+    pub fn from_ast(ast: &Spanned<Ast>) -> Result<Spanned<Stmt>, ParseError> {
+        let stmt = match &ast.inner {
+            Ast::Ass { ident, value } => match Category::of(&value.inner) {
+                Category::Stmt => Stmt::Define {
+                    ident: ident.clone(),
+                    body: Box::new(Stmt::from_ast(value)?),
+                },
+                _ => Stmt::Ass {
+                    ident: ident.clone(),
+                    value: Box::new(AExp::from_ast(value)?),
+                },
+            },
+            Ast::Skip => Stmt::Skip,
+            Ast::Comp { first, second } => Stmt::Comp {
+                first: Box::new(Stmt::from_ast(first)?),
+                second: Box::new(Stmt::from_ast(second)?),
+            },
+            Ast::If {
+                cond,
+                true_path,
+                false_path,
+            } => Stmt::If {
+                cond: Box::new(BExp::from_ast(cond)?),
+                true_path: Box::new(Stmt::from_ast(true_path)?),
+                false_path: Box::new(Stmt::from_ast(false_path)?),
+            },
+            Ast::While {
+                cond,
+                body,
+                invariant,
+                variant,
+            } => Stmt::While {
+                cond: Box::new(BExp::from_ast(cond)?),
+                body: Box::new(Stmt::from_ast(body)?),
+                invariant: invariant
+                    .as_deref()
+                    .map(BExp::from_ast)
+                    .transpose()?
+                    .map(Box::new),
+                variant: variant
+                    .as_deref()
+                    .map(AExp::from_ast)
+                    .transpose()?
+                    .map(Box::new),
+            },
+            Ast::DefinitionRun { ident } => Stmt::DefinitionRun {
+                ident: ident.clone(),
+            },
+            Ast::Procedure {
+                ident,
+                inputs,
+                outputs,
+                body,
+            } => Stmt::Procedure {
+                ident: ident.clone(),
+                inputs: inputs.clone(),
+                outputs: outputs.clone(),
+                body: Box::new(Stmt::from_ast(body)?),
+            },
+            Ast::Call {
+                ident,
+                inputs,
+                outputs,
+            } => Stmt::Call {
+                ident: ident.clone(),
+                inputs: inputs
+                    .iter()
+                    .map(AExp::from_ast)
+                    .collect::<Result<_, _>>()?,
+                outputs: outputs.clone(),
+            },
+            Ast::Block { declarations, body } => Stmt::Block {
+                declarations: declarations
+                    .iter()
+                    .map(|(ident, value)| Ok((ident.clone(), AExp::from_ast(value)?)))
+                    .collect::<Result<_, _>>()?,
+                body: Box::new(Stmt::from_ast(body)?),
+            },
+            Ast::Assert { cond } => Stmt::Assert {
+                cond: Box::new(BExp::from_ast(cond)?),
+            },
+            Ast::Assume { cond } => Stmt::Assume {
+                cond: Box::new(BExp::from_ast(cond)?),
+            },
+            Ast::Assertion { formula } => Stmt::Assertion {
+                formula: Box::new(BExp::from_ast(formula)?),
+            },
+            Ast::Triple { pre, stmt, post } => Stmt::Triple {
+                pre: Box::new(BExp::from_ast(pre)?),
+                stmt: Box::new(Stmt::from_ast(stmt)?),
+                post: Box::new(BExp::from_ast(post)?),
+            },
+            Ast::Error => {
+                return Err(ParseError::new(
+                    ParseErrorKind::Unparsed,
+                    "Cannot convert code that failed to parse".to_string(),
+                    ast.span,
+                ))
+            }
+            _ => return Err(wrong_category(ast, Category::Stmt)),
+        };
+
+        Ok(Spanned::new(stmt, ast.span))
+    }
+}
+
+impl AExp {
+    /// Converts an arithmetic expression of the `Ast`, checking that every
+    /// part of it is of the right category.
+    pub fn from_ast(ast: &Spanned<Ast>) -> Result<Spanned<AExp>, ParseError> {
+        let aexp = match &ast.inner {
+            Ast::Literal(value) => AExp::Literal(value.clone()),
+            Ast::Ident(ident) => AExp::Ident(ident.clone()),
+            Ast::Add { left, right } => AExp::Add {
+                left: Box::new(AExp::from_ast(left)?),
+                right: Box::new(AExp::from_ast(right)?),
+            },
+            Ast::Sub { left, right } => AExp::Sub {
+                left: Box::new(AExp::from_ast(left)?),
+                right: Box::new(AExp::from_ast(right)?),
+            },
+            Ast::Mul { left, right } => AExp::Mul {
+                left: Box::new(AExp::from_ast(left)?),
+                right: Box::new(AExp::from_ast(right)?),
+            },
+            Ast::Div { left, right } => AExp::Div {
+                left: Box::new(AExp::from_ast(left)?),
+                right: Box::new(AExp::from_ast(right)?),
+            },
+            Ast::Mod { left, right } => AExp::Mod {
+                left: Box::new(AExp::from_ast(left)?),
+                right: Box::new(AExp::from_ast(right)?),
+            },
+            _ => return Err(wrong_category(ast, Category::AExp)),
+        };
+
+        Ok(Spanned::new(aexp, ast.span))
+    }
+}
+
+impl BExp {
+    /// Converts a boolean expression of the `Ast`, checking that every part of
+    /// it is of the right category.
+    ///
+    /// An equality is between numbers unless its left side is a boolean.
+    pub fn from_ast(ast: &Spanned<Ast>) -> Result<Spanned<BExp>, ParseError> {
+        let bexp = match &ast.inner {
+            Ast::True => BExp::True,
+            Ast::False => BExp::False,
+            Ast::Not { expr, .. } => BExp::Not {
+                expr: Box::new(BExp::from_ast(expr)?),
+            },
+            Ast::And { left, right } => BExp::And {
+                left: Box::new(BExp::from_ast(left)?),
+                right: Box::new(BExp::from_ast(right)?),
+            },
+            Ast::Eq { left, right } if Category::of(&left.inner) == Category::BExp => {
+                BExp::BoolEq {
+                    left: Box::new(BExp::from_ast(left)?),
+                    right: Box::new(BExp::from_ast(right)?),
+                }
+            }
+            Ast::Eq { left, right } => BExp::Eq {
+                left: Box::new(AExp::from_ast(left)?),
+                right: Box::new(AExp::from_ast(right)?),
+            },
+            Ast::LessEq { left, right } => BExp::LessEq {
+                left: Box::new(AExp::from_ast(left)?),
+                right: Box::new(AExp::from_ast(right)?),
+            },
+            _ => return Err(wrong_category(ast, Category::BExp)),
+        };
+
+        Ok(Spanned::new(bexp, ast.span))
+    }
+}
+
+fn wrong_category(ast: &Spanned<Ast>, expected: Category) -> ParseError {
+    ParseError::wrong_category(expected, Category::of(&ast.inner), ast.span)
+}
+
+/// Compares two statements, ignoring their spans.
+impl PartialEq for Spanned<Stmt> {
+    fn eq(&self, other: &Self) -> bool {
+        self.inner == other.inner
+    }
+}
+
+impl Eq for Spanned<Stmt> {}
+
+/// Compares two arithmetic expressions, ignoring their spans.
+impl PartialEq for Spanned<AExp> {
+    fn eq(&self, other: &Self) -> bool {
+        self.inner == other.inner
+    }
+}
+
+impl Eq for Spanned<AExp> {}
+
+/// Compares two boolean expressions, ignoring their spans.
+impl PartialEq for Spanned<BExp> {
+    fn eq(&self, other: &Self) -> bool {
+        self.inner == other.inner
+    }
+}
+
+impl Eq for Spanned<BExp> {}
+
+/// Gives a node an empty span, for trees built by hand.
+impl From<Stmt> for Spanned<Stmt> {
+    fn from(stmt: Stmt) -> Self {
+        Spanned::new(stmt, Span::default())
+    }
+}
+
+impl From<AExp> for Spanned<AExp> {
+    fn from(aexp: AExp) -> Self {
+        Spanned::new(aexp, Span::default())
+    }
+}
+
+impl From<BExp> for Spanned<BExp> {
+    fn from(bexp: BExp) -> Self {
+        Spanned::new(bexp, Span::default())
+    }
+}
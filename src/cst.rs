@@ -0,0 +1,31 @@
+//! A module for the lossless concrete syntax tree (CST) of While programs.
+//!
+//! Unlike the [`Ast`][ast], the CST keeps every token of the source, including
+//! whitespace, comments, semicolons and parentheses, so that the source can be
+//! rebuilt from it exactly. This is what tools that edit programs need, such
+//! as formatters, so that the author's layout isn't lost.
+//!
+//! The tree comes in two layers, in the style of Roslyn and rust-analyzer:
+//!
+//! - The green tree, made of [`GreenNode`]s and [`GreenToken`]s, is immutable
+//!   and only knows the kinds and text of its parts. Identical subtrees can be
+//!   shared, and editing a tree only needs the path to the edit to be rebuilt.
+//! - The red tree, made of [`SyntaxNode`]s and [`SyntaxToken`]s, is built on
+//!   demand over a green tree. It adds parent pointers and the [`Span`][span]
+//!   of everything in it.
+//!
+//! Use [`Parser::parse_with_cst`][parse_with_cst] to get a CST alongside the
+//! `Ast`, or a [`GreenNodeBuilder`] to build one by hand.
+//!
+//! [ast]: crate::ast::Ast
+//! [span]: crate::lexer::Span
+//! [parse_with_cst]: crate::parser::Parser::parse_with_cst
+mod builder;
+mod green;
+mod node_kind;
+mod syntax;
+
+pub use crate::cst::builder::{Checkpoint, GreenNodeBuilder};
+pub use crate::cst::green::{GreenElement, GreenNode, GreenToken};
+pub use crate::cst::node_kind::NodeKind;
+pub use crate::cst::syntax::{SyntaxElement, SyntaxNode, SyntaxToken};
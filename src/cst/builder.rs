@@ -0,0 +1,93 @@
+use crate::cst::{GreenElement, GreenNode, GreenToken, NodeKind};
+use crate::lexer::Token;
+
+/// Builds a green tree from the top down, as a parser would.
+///
+/// Nodes are opened with [`start_node`](GreenNodeBuilder::start_node), filled
+/// with tokens and child nodes, then closed with
+/// [`finish_node`](GreenNodeBuilder::finish_node). When the kind of a node is
+/// only known after some of its children have been built, like the operator of
+/// a binary expression, take a [`Checkpoint`] before them and use
+/// [`start_node_at`](GreenNodeBuilder::start_node_at) instead.
+#[derive(Debug, Default)]
+pub struct GreenNodeBuilder {
+    /// The nodes that are still open, along with the index of their first
+    /// child in `children`.
+    parents: Vec<(NodeKind, usize)>,
+    children: Vec<GreenElement>,
+}
+
+/// A position in a [`GreenNodeBuilder`] that a node can be started at later.
+#[derive(Copy, Clone, Debug)]
+pub struct Checkpoint(usize);
+
+impl GreenNodeBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Opens a new node, which everything added until the matching
+    /// `finish_node` goes into.
+    pub fn start_node(&mut self, kind: NodeKind) {
+        self.parents.push((kind, self.children.len()));
+    }
+
+    /// Adds a token to the current node.
+    pub fn token(&mut self, kind: Token, text: &str) {
+        self.children
+            .push(GreenToken::new(kind, text.to_string()).into());
+    }
+
+    /// Closes the current node.
+    ///
+    /// # Panics
+    /// Panics if there is no open node.
+    pub fn finish_node(&mut self) {
+        let (kind, first_child) = self.parents.pop().expect("no node to finish");
+        let children = self.children.split_off(first_child);
+
+        self.children.push(GreenNode::new(kind, children).into());
+    }
+
+    /// Remembers the current position, so that a node can be started there
+    /// later on.
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint(self.children.len())
+    }
+
+    /// Opens a new node at a checkpoint, so that everything added since the
+    /// checkpoint becomes its children.
+    ///
+    /// # Panics
+    /// Panics if the checkpoint is from before the current node was opened.
+    pub fn start_node_at(&mut self, Checkpoint(index): Checkpoint, kind: NodeKind) {
+        assert!(
+            self.parents.last().map_or(0, |&(_, first)| first) <= index,
+            "checkpoint is outside of the current node"
+        );
+
+        self.parents.push((kind, index));
+    }
+
+    /// The number of nodes that are still open.
+    pub fn depth(&self) -> usize {
+        self.parents.len()
+    }
+
+    /// Closes every node that is still open, and returns the root.
+    ///
+    /// # Panics
+    /// Panics if the tree doesn't have exactly one root node.
+    pub fn finish(mut self) -> GreenNode {
+        while !self.parents.is_empty() {
+            self.finish_node();
+        }
+
+        match (self.children.pop(), self.children.is_empty()) {
+            (Some(GreenElement::Node(root)), true) => {
+                std::rc::Rc::try_unwrap(root).unwrap_or_else(|root| (*root).clone())
+            }
+            _ => panic!("a green tree must have exactly one root node"),
+        }
+    }
+}
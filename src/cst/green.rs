@@ -0,0 +1,126 @@
+use crate::cst::NodeKind;
+use crate::lexer::Token;
+use std::fmt::{Display, Formatter};
+use std::rc::Rc;
+
+/// An immutable node of the green tree. It knows its kind, its children and
+/// the length of its text, but not where it is in the source.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct GreenNode {
+    kind: NodeKind,
+    len: usize,
+    children: Vec<GreenElement>,
+}
+
+impl GreenNode {
+    pub fn new(kind: NodeKind, children: Vec<GreenElement>) -> Self {
+        let len = children.iter().map(GreenElement::len).sum();
+
+        Self {
+            kind,
+            len,
+            children,
+        }
+    }
+
+    pub fn kind(&self) -> NodeKind {
+        self.kind
+    }
+
+    /// The length of this node's text, in bytes.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn children(&self) -> &[GreenElement] {
+        &self.children
+    }
+}
+
+/// Writes out the exact source text of the node.
+impl Display for GreenNode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for child in &self.children {
+            match child {
+                GreenElement::Node(node) => write!(f, "{node}")?,
+                GreenElement::Token(token) => write!(f, "{token}")?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// An immutable token of the green tree, along with its source text.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct GreenToken {
+    kind: Token,
+    text: String,
+}
+
+impl GreenToken {
+    pub fn new(kind: Token, text: String) -> Self {
+        Self { kind, text }
+    }
+
+    pub fn kind(&self) -> Token {
+        self.kind
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// The length of this token's text, in bytes.
+    pub fn len(&self) -> usize {
+        self.text.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+}
+
+impl Display for GreenToken {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.text)
+    }
+}
+
+/// A child of a [`GreenNode`]. Children are reference counted, so that they
+/// can be shared between trees.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum GreenElement {
+    Node(Rc<GreenNode>),
+    Token(Rc<GreenToken>),
+}
+
+impl GreenElement {
+    /// The length of this element's text, in bytes.
+    pub fn len(&self) -> usize {
+        match self {
+            GreenElement::Node(node) => node.len(),
+            GreenElement::Token(token) => token.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl From<GreenNode> for GreenElement {
+    fn from(node: GreenNode) -> Self {
+        GreenElement::Node(Rc::new(node))
+    }
+}
+
+impl From<GreenToken> for GreenElement {
+    fn from(token: GreenToken) -> Self {
+        GreenElement::Token(Rc::new(token))
+    }
+}
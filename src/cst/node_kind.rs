@@ -0,0 +1,54 @@
+/// The kind of a node in the CST. Tokens use [`Token`][token] as their kind
+/// instead.
+///
+/// Nodes follow the grammar that the parser uses, so each one corresponds to
+/// one of its rules.
+///
+/// [token]: crate::lexer::Token
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum NodeKind {
+    /// The whole program, including any leading and trailing trivia.
+    Root,
+
+    // Statements
+    /// Statements separated by semicolons.
+    StmtBlock,
+    /// A statement block in parentheses.
+    ParenStmt,
+    /// A statement block in `{ }` or `begin ... end`.
+    DelimitedBlock,
+    /// A `begin ... end` block that declares local variables.
+    LocalBlock,
+    /// A local variable's declaration, like `var x := 1;`.
+    Declaration,
+    IfStmt,
+    WhileStmt,
+    /// An assignment, or a definition when the right-hand side is in `[[ ]]`.
+    AssStmt,
+    DefinitionRun,
+    /// A definition with parameters, like `Add(a, b; r) := [[r := a + b]]`.
+    Procedure,
+    /// A run of a definition with arguments, like `Add(x, 3; z)`.
+    Call,
+    /// The arguments of a call, or the parameters of a definition, in `( )`.
+    ArgList,
+    SkipStmt,
+    /// An `assert` or `assume` statement.
+    CheckStmt,
+    /// A formula in `{ }`, on its own or around a statement.
+    Assertion,
+    /// A statement with an assertion before and after it.
+    Triple,
+
+    // Expressions
+    /// A binary operator and its operands.
+    BinaryExpr,
+    /// A prefix operator and its operand.
+    UnaryExpr,
+    /// An expression in parentheses.
+    ParenExpr,
+    /// An integer literal, `true` or `false`.
+    Literal,
+    /// A variable being read.
+    Name,
+}
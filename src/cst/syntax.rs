@@ -0,0 +1,198 @@
+use crate::cst::{GreenElement, GreenNode, GreenToken, NodeKind};
+use crate::lexer::{Span, Token};
+use std::fmt::{Debug, Display, Formatter};
+use std::rc::Rc;
+
+/// A node of the red tree: a [`GreenNode`] along with its position in the
+/// source and its parent.
+///
+/// Red nodes are cheap to clone, and are made on demand while walking the
+/// tree, so only the parts of the tree that are looked at are ever built.
+#[derive(Clone)]
+pub struct SyntaxNode(Rc<NodeData>);
+
+struct NodeData {
+    green: Rc<GreenNode>,
+    offset: usize,
+    parent: Option<SyntaxNode>,
+}
+
+impl SyntaxNode {
+    /// Makes the root of a red tree, starting at the start of the source.
+    pub fn new_root(green: GreenNode) -> Self {
+        Self::new(Rc::new(green), 0, None)
+    }
+
+    fn new(green: Rc<GreenNode>, offset: usize, parent: Option<SyntaxNode>) -> Self {
+        Self(Rc::new(NodeData {
+            green,
+            offset,
+            parent,
+        }))
+    }
+
+    pub fn kind(&self) -> NodeKind {
+        self.0.green.kind()
+    }
+
+    pub fn span(&self) -> Span {
+        Span(self.0.offset, self.0.offset + self.0.green.len())
+    }
+
+    pub fn green(&self) -> &GreenNode {
+        &self.0.green
+    }
+
+    pub fn parent(&self) -> Option<SyntaxNode> {
+        self.0.parent.clone()
+    }
+
+    /// The exact source text of this node, trivia and all.
+    pub fn text(&self) -> String {
+        self.0.green.to_string()
+    }
+
+    /// Iterates over the child nodes and tokens, in source order.
+    pub fn children_with_tokens(&self) -> impl Iterator<Item = SyntaxElement> + '_ {
+        let mut offset = self.0.offset;
+
+        self.0.green.children().iter().map(move |child| {
+            let element = match child {
+                GreenElement::Node(node) => {
+                    SyntaxElement::Node(SyntaxNode::new(node.clone(), offset, Some(self.clone())))
+                }
+                GreenElement::Token(token) => SyntaxElement::Token(SyntaxToken {
+                    green: token.clone(),
+                    offset,
+                    parent: self.clone(),
+                }),
+            };
+
+            offset += child.len();
+            element
+        })
+    }
+
+    /// Iterates over the child nodes, in source order, skipping tokens.
+    pub fn children(&self) -> impl Iterator<Item = SyntaxNode> + '_ {
+        self.children_with_tokens()
+            .filter_map(|element| match element {
+                SyntaxElement::Node(node) => Some(node),
+                SyntaxElement::Token(_) => None,
+            })
+    }
+
+    /// Every token within this node, in source order.
+    pub fn descendant_tokens(&self) -> Vec<SyntaxToken> {
+        let mut tokens = Vec::new();
+
+        for element in self.children_with_tokens() {
+            match element {
+                SyntaxElement::Node(node) => tokens.extend(node.descendant_tokens()),
+                SyntaxElement::Token(token) => tokens.push(token),
+            }
+        }
+
+        tokens
+    }
+
+    /// Every node within this node, including itself, in source order.
+    pub fn descendants(&self) -> Vec<SyntaxNode> {
+        let mut nodes = vec![self.clone()];
+
+        for child in self.children() {
+            nodes.extend(child.descendants());
+        }
+
+        nodes
+    }
+
+    fn fmt_tree(&self, f: &mut Formatter<'_>, indent: usize) -> std::fmt::Result {
+        writeln!(f, "{:indent$}{self:?}", "")?;
+
+        for element in self.children_with_tokens() {
+            match element {
+                SyntaxElement::Node(node) => node.fmt_tree(f, indent + 2)?,
+                SyntaxElement::Token(token) => writeln!(f, "{:1$}{token:?}", "", indent + 2)?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Writes the kind and span of the node, like `IfStmt@0..21`. The alternate
+/// form, `{:#?}`, writes out the whole tree instead, one element per line.
+impl Debug for SyntaxNode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if f.alternate() {
+            return self.fmt_tree(f, 0);
+        }
+
+        let Span(start, end) = self.span();
+        write!(f, "{:?}@{start}..{end}", self.kind())
+    }
+}
+
+impl Display for SyntaxNode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0.green)
+    }
+}
+
+/// A token of the red tree: a [`GreenToken`] along with its position in the
+/// source and its parent.
+#[derive(Clone)]
+pub struct SyntaxToken {
+    green: Rc<GreenToken>,
+    offset: usize,
+    parent: SyntaxNode,
+}
+
+impl SyntaxToken {
+    pub fn kind(&self) -> Token {
+        self.green.kind()
+    }
+
+    pub fn text(&self) -> &str {
+        self.green.text()
+    }
+
+    pub fn span(&self) -> Span {
+        Span(self.offset, self.offset + self.green.len())
+    }
+
+    pub fn parent(&self) -> SyntaxNode {
+        self.parent.clone()
+    }
+}
+
+/// Writes the kind, span and text of the token, like `If@0..2 "if"`.
+impl Debug for SyntaxToken {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let Span(start, end) = self.span();
+        write!(f, "{:?}@{start}..{end} {:?}", self.kind(), self.text())
+    }
+}
+
+impl Display for SyntaxToken {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.text())
+    }
+}
+
+/// Either a [`SyntaxNode`] or a [`SyntaxToken`].
+#[derive(Clone, Debug)]
+pub enum SyntaxElement {
+    Node(SyntaxNode),
+    Token(SyntaxToken),
+}
+
+impl SyntaxElement {
+    pub fn span(&self) -> Span {
+        match self {
+            SyntaxElement::Node(node) => node.span(),
+            SyntaxElement::Token(token) => token.span(),
+        }
+    }
+}
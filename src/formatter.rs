@@ -0,0 +1,200 @@
+//! A module for formatting programs: writing an `Ast` back out as source code,
+//! one statement per line, with the bodies of `if` and `while` statements
+//! indented.
+//!
+//! Programs can be written in either [`BlockStyle`], whatever style they were
+//! parsed in, so formatting is also how a program is converted from one style
+//! to the other. Comments aren't part of the `Ast`, so they aren't kept.
+
+use crate::ast::{write_annotations, write_arg_list, Ast};
+use crate::parser::BlockStyle;
+use std::fmt::Write;
+
+const INDENT: &str = "    ";
+
+/// Formats a statement block in the given block style, ending with a line
+/// break. Expressions are written as by the `Display` impl of [`Ast`].
+pub fn format(ast: &Ast, style: BlockStyle) -> String {
+    let mut formatter = Formatter {
+        style,
+        output: String::new(),
+    };
+
+    formatter.stmt_block(ast, 0);
+    formatter.output.push('\n');
+    formatter.output
+}
+
+struct Formatter {
+    style: BlockStyle,
+    output: String,
+}
+
+impl Formatter {
+    fn line_break(&mut self, depth: usize) {
+        self.output.push('\n');
+        self.output.push_str(&INDENT.repeat(depth));
+    }
+
+    /// Writes statements one per line, separated by `;`s. In the greedy style,
+    /// an `if` or `while` that has another statement after it is bracketed,
+    /// so that its last body doesn't take that statement.
+    fn stmt_block(&mut self, ast: &Ast, depth: usize) {
+        let mut stmts = Vec::new();
+        flatten(ast, &mut stmts);
+
+        for (i, stmt) in stmts.iter().enumerate() {
+            if i > 0 {
+                self.output.push(';');
+                self.line_break(depth);
+            }
+
+            let followed = i + 1 < stmts.len();
+
+            if followed
+                && self.style == BlockStyle::Greedy
+                && matches!(stmt, Ast::If { .. } | Ast::While { .. })
+            {
+                self.output.push('(');
+                self.statement(stmt, depth);
+                self.line_break(depth);
+                self.output.push(')');
+            } else {
+                self.statement(stmt, depth);
+            }
+        }
+    }
+
+    fn statement(&mut self, ast: &Ast, depth: usize) {
+        match ast {
+            Ast::If {
+                cond,
+                true_path,
+                false_path,
+            } => {
+                let _ = write!(self.output, "if {} then", cond.inner);
+                self.body(&true_path.inner, depth);
+
+                match self.style {
+                    BlockStyle::Greedy => {
+                        self.line_break(depth);
+                        self.output.push_str("else");
+                    }
+                    BlockStyle::Delimited => self.output.push_str(" else"),
+                }
+
+                self.body(&false_path.inner, depth);
+            }
+            Ast::While {
+                cond,
+                body,
+                invariant,
+                variant,
+            } => {
+                let _ = write!(self.output, "while {}", cond.inner);
+                let _ =
+                    write_annotations(&mut self.output, invariant.as_deref(), variant.as_deref());
+                self.output.push_str(" do");
+                self.body(&body.inner, depth);
+            }
+            Ast::Ass { ident, value } if value.inner.is_statement() => {
+                let _ = write!(self.output, "{ident}");
+                self.definition(&value.inner, depth);
+            }
+            Ast::Procedure {
+                ident,
+                inputs,
+                outputs,
+                body,
+            } => {
+                let _ = write!(self.output, "{ident}");
+                let _ = write_arg_list(&mut self.output, inputs, outputs);
+                self.definition(&body.inner, depth);
+            }
+            Ast::Triple { pre, stmt, post } => {
+                let _ = write!(self.output, "{{{}}} ", pre.inner);
+
+                // The assertion after the statement would otherwise be taken
+                // as part of it.
+                let bracketed = match stmt.inner {
+                    Ast::Comp { .. } => true,
+                    Ast::If { .. } | Ast::While { .. } => self.style == BlockStyle::Greedy,
+                    _ => false,
+                };
+
+                if bracketed {
+                    self.output.push('(');
+                    self.line_break(depth + 1);
+                    self.stmt_block(&stmt.inner, depth + 1);
+                    self.line_break(depth);
+                    self.output.push(')');
+                } else {
+                    self.statement(&stmt.inner, depth);
+                }
+
+                let _ = write!(self.output, " {{{}}}", post.inner);
+            }
+            Ast::Block { declarations, body } => {
+                self.output.push_str("begin");
+
+                for (ident, value) in declarations {
+                    self.line_break(depth + 1);
+                    let _ = write!(self.output, "var {ident} := {};", value.inner);
+                }
+
+                self.line_break(depth + 1);
+                self.stmt_block(&body.inner, depth + 1);
+                self.line_break(depth);
+                self.output.push_str("end");
+            }
+            Ast::Comp { .. } => self.stmt_block(ast, depth),
+            _ => {
+                let _ = write!(self.output, "{ast}");
+            }
+        }
+    }
+
+    /// Writes the `:=` and body of a definition, after its name and any
+    /// parameters.
+    fn definition(&mut self, body: &Ast, depth: usize) {
+        self.output.push_str(" := [[");
+
+        // Definitions of a single simple statement stay on one line.
+        if matches!(body, Ast::Comp { .. } | Ast::If { .. } | Ast::While { .. }) {
+            self.line_break(depth + 1);
+            self.stmt_block(body, depth + 1);
+            self.line_break(depth);
+        } else {
+            self.statement(body, depth);
+        }
+
+        self.output.push_str("]]");
+    }
+
+    /// Writes the body of an `if` or `while` statement on the lines after it,
+    /// indented, and in `{ }` in the delimited style.
+    fn body(&mut self, ast: &Ast, depth: usize) {
+        if self.style == BlockStyle::Delimited {
+            self.output.push_str(" {");
+        }
+
+        self.line_break(depth + 1);
+        self.stmt_block(ast, depth + 1);
+
+        if self.style == BlockStyle::Delimited {
+            self.line_break(depth);
+            self.output.push('}');
+        }
+    }
+}
+
+/// Collects the statements of a composition in the order they run.
+fn flatten<'a>(ast: &'a Ast, stmts: &mut Vec<&'a Ast>) {
+    match ast {
+        Ast::Comp { first, second } => {
+            flatten(&first.inner, stmts);
+            flatten(&second.inner, stmts);
+        }
+        _ => stmts.push(ast),
+    }
+}
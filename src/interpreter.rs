@@ -1,67 +1,179 @@
 use crate::ast::{Ast, Value};
 use crate::interpreter::state::State;
+use crate::lexer::{Span, Spanned};
 
-mod context;
+pub mod context;
 pub mod interpret_error;
+pub mod scoping;
 pub mod state;
 
 use crate::ast::Value::*;
-use crate::interpreter::context::Context;
+use crate::interpreter::context::{Context, Definition, Definitions};
 
-use crate::interpreter::interpret_error::InterpretError;
+use crate::interpreter::interpret_error::{InterpretError, InterpretErrorKind};
+use crate::interpreter::scoping::Scoping;
+use crate::number::{Integer, IntegerType, Rounding};
+use std::rc::Rc;
 
 /// A tree-walk interpreter. The interpreter doesn't
 /// modify the AST.
 pub struct Interpreter {
     context: Context,
-    ast: Ast,
+    ast: Spanned<Ast>,
+    integer_type: IntegerType,
+    rounding: Rounding,
+    /// Whether loop invariants and variants are checked as the program runs.
+    check_annotations: bool,
+    scoping: Scoping,
 }
 
 impl Interpreter {
-    pub fn new(ast: Ast) -> Self {
+    pub fn new(ast: Spanned<Ast>) -> Self {
         Self {
             context: Context::new(),
             ast,
+            integer_type: IntegerType::default(),
+            rounding: Rounding::default(),
+            check_annotations: false,
+            scoping: Scoping::default(),
         }
     }
 
+    /// Creates an interpreter whose run starts from the given state, rather
+    /// than from the empty state.
+    pub fn with_state(ast: Spanned<Ast>, state: State) -> Self {
+        Self {
+            context: Context::with_state(state),
+            ast,
+            integer_type: IntegerType::default(),
+            rounding: Rounding::default(),
+            check_annotations: false,
+            scoping: Scoping::default(),
+        }
+    }
+
+    /// Sets the range of integers that arithmetic must stay within. Results
+    /// outside of this range are reported as overflow errors.
+    pub fn set_integer_type(&mut self, integer_type: IntegerType) {
+        self.integer_type = integer_type;
+    }
+
+    /// Sets how `/` and `%` round a quotient that isn't whole.
+    pub fn set_rounding(&mut self, rounding: Rounding) {
+        self.rounding = rounding;
+    }
+
+    /// Sets which definitions the body of a definition sees when it runs.
+    /// Definitions capture what they see under static scoping when they're
+    /// defined, so this should be set before the run starts.
+    pub fn set_scoping(&mut self, scoping: Scoping) {
+        self.scoping = scoping;
+    }
+
+    /// Sets whether loop annotations are checked. When they are, a loop's
+    /// invariant must hold before every iteration and once the loop finishes,
+    /// and its variant must not be negative before an iteration, and must
+    /// decrease over each one.
+    pub fn set_check_annotations(&mut self, check_annotations: bool) {
+        self.check_annotations = check_annotations;
+    }
+
     pub fn interpret(&mut self) -> Result<State, InterpretError> {
         self.interpret_ast(&self.ast.clone())?;
 
         Ok(self.context.state.clone())
     }
 
-    fn interpret_ast(&mut self, ast: &Ast) -> Result<Value, InterpretError> {
-        match ast {
+    /// Runs a statement against the interpreter's current context, keeping
+    /// any variables and definitions from previous runs.
+    pub fn execute(&mut self, statement: &Spanned<Ast>) -> Result<(), InterpretError> {
+        self.interpret_ast(statement)?;
+        Ok(())
+    }
+
+    /// Evaluates an expression against the interpreter's current context.
+    pub fn evaluate(&mut self, expression: &Spanned<Ast>) -> Result<Value, InterpretError> {
+        self.interpret_ast(expression)
+    }
+
+    pub fn context(&self) -> &Context {
+        &self.context
+    }
+
+    fn interpret_ast(&mut self, ast: &Spanned<Ast>) -> Result<Value, InterpretError> {
+        // Errors point at the node that failed, unless there's a more specific
+        // part of it to blame.
+        let error = |message: &str| Err(InterpretError::new(message.to_string(), ast.span));
+
+        match &ast.inner {
             Ast::Ass { ident, value } => {
                 match value {
-                    x if !x.is_statement() => match self.interpret_ast(value) {
-                        Ok(I32(x)) => self.context.set_variable(ident.clone(), x),
-                        _ => return Err(InterpretError(format!("Bad RHS of Assign: {:?}", value))),
+                    x if !x.inner.is_statement() => match self.interpret_ast(value)? {
+                        Int(x) => self.context.set_variable(ident.clone(), x),
+                        _ => return Err(InterpretError::new(format!("Bad RHS of Assign: {:?}", value.inner), value.span)),
                     },
 
-                    x if x.is_statement() => self
-                        .context
-                        .add_definition(ident.to_string(), *value.clone()),
+                    x if x.inner.is_statement() => {
+                        self.define(ident, Vec::new(), Vec::new(), value)
+                    }
 
-                    _ => return Err(InterpretError("Bad RHS of expression".to_string())),
+                    _ => return error("Bad RHS of expression"),
                 };
                 Ok(Unit)
             }
 
             Ast::DefinitionRun { ident } => {
-                let referenced_ast = self.context.get_definition(ident);
-
-                match referenced_ast {
-                    Some(existing_ast) => Ok(self.interpret_ast(&existing_ast.clone())?),
-                    None => Err(InterpretError(format!(
-                        "Definition {} not defined yet",
-                        ident
-                    ))),
-                }
+                self.call(ident, &[], &[], ast.span)?;
+                Ok(Unit)
+            }
+            Ast::Procedure {
+                ident,
+                inputs,
+                outputs,
+                body,
+            } => {
+                self.define(ident, inputs.clone(), outputs.clone(), body);
+                Ok(Unit)
+            }
+            Ast::Call {
+                ident,
+                inputs,
+                outputs,
+            } => {
+                self.call(ident, inputs, outputs, ast.span)?;
+                Ok(Unit)
             }
 
             Ast::Skip => Ok(Unit),
+            // Assertions are for reasoning about the program, not for running.
+            Ast::Assertion { .. } => Ok(Unit),
+            Ast::Triple { stmt, .. } => self.interpret_ast(stmt),
+            Ast::Block { declarations, body } => {
+                // Definitions made in the block are local to it too.
+                let outer = self.context.definitions.clone();
+
+                self.context.start_scope();
+                let result = self.interpret_block(declarations, body);
+                self.context.end_scope();
+
+                self.context.definitions = outer;
+                result
+            }
+            Ast::Assert { cond } => self.check_condition(
+                cond,
+                InterpretErrorKind::AssertionFailed,
+                format!("Assertion failed: {}", cond.inner),
+            ),
+            Ast::Assume { cond } => self.check_condition(
+                cond,
+                InterpretErrorKind::Infeasible,
+                format!(
+                    "Assumption doesn't hold, so the run is infeasible: {}",
+                    cond.inner
+                ),
+            ),
+
+            Ast::Error => error("Cannot run code that failed to parse"),
 
             Ast::Comp { first, second } => {
                 self.interpret_ast(first)?;
@@ -76,22 +188,71 @@ impl Interpreter {
             } => match self.interpret_ast(cond)? {
                 Bool(true) => self.interpret_ast(true_path),
                 Bool(false) => self.interpret_ast(false_path),
-                I32(_) => Err(InterpretError(
+                Int(_) => Err(InterpretError::new(
                     "Arithmetic conditional not allowed".to_string(),
+                    cond.span,
                 )),
-                Unit => Err(InterpretError(
+                Unit => Err(InterpretError::new(
                     "Statement conditional not allowed".to_string(),
+                    cond.span,
                 )),
             },
 
-            Ast::While { cond, body } => {
+            Ast::While {
+                cond,
+                body,
+                invariant,
+                variant,
+            } => {
+                let mut iterations = 0;
+
                 loop {
+                    // Checking before the condition means that the last check
+                    // is the one once the loop has finished.
+                    self.check_invariant(invariant.as_deref(), iterations)?;
+
                     match self.interpret_ast(cond) {
-                        Ok(Bool(true)) => self.interpret_ast(body),
+                        Ok(Bool(true)) => Ok(Unit),
                         Ok(Bool(false)) => break,
-                        Ok(_) => Err(InterpretError("Bad conditional".to_string())),
+                        Ok(_) => Err(InterpretError::new(
+                            "Bad conditional".to_string(),
+                            cond.span,
+                        )),
                         err @ Err(_) => err,
                     }?;
+
+                    let before = self.evaluate_variant(variant.as_deref())?;
+
+                    if let (Some(variant), Some(before)) = (variant, &before) {
+                        if before.is_negative() {
+                            return Err(InterpretError::with_state(
+                                InterpretErrorKind::VariantNegative { iterations },
+                                format!(
+                                    "Loop variant is negative before iteration {}: it is {before}",
+                                    iterations + 1
+                                ),
+                                variant.span,
+                                self.context.state.clone(),
+                            ));
+                        }
+                    }
+
+                    self.interpret_ast(body)?;
+                    iterations += 1;
+                    let after = self.evaluate_variant(variant.as_deref())?;
+
+                    if let (Some(variant), Some(before), Some(after)) = (variant, before, after) {
+                        if after >= before {
+                            return Err(InterpretError::with_state(
+                                InterpretErrorKind::VariantNotDecreasing { iterations },
+                                format!(
+                                    "Loop variant didn't decrease in iteration {iterations}: it went from {before} to {after}"
+                                ),
+                                variant.span,
+                                self.context.state.clone(),
+                            ));
+                        }
+                    }
                 }
 
                 Ok(Unit)
@@ -100,15 +261,14 @@ impl Interpreter {
             Ast::True => Ok(Bool(true)),
             Ast::False => Ok(Bool(false)),
 
-            Ast::Not { expr } => {
+            Ast::Not { expr, .. } => {
                 let inner = self.interpret_ast(expr);
 
                 match inner {
                     Ok(Bool(b)) => Ok(Bool(!b)),
-                    Ok(I32(_)) => Err(InterpretError("Cannot negate arithmetic".to_string())),
-                    _ => Err(InterpretError(
-                        "Bool did not evaluate correctly".to_string(),
-                    )),
+                    Ok(Int(_)) => error("Cannot negate arithmetic"),
+                    Err(e) => Err(e),
+                    _ => error("Bool did not evaluate correctly"),
                 }
             }
             Ast::Eq { left, right } => {
@@ -116,54 +276,335 @@ impl Interpreter {
                 let right_result = self.interpret_ast(right);
 
                 match (left_result, right_result) {
-                    (Ok(I32(l)), Ok(I32(r))) => Ok(Bool(l == r)),
+                    (Ok(Int(l)), Ok(Int(r))) => Ok(Bool(l == r)),
 
                     (Ok(Bool(l)), Ok(Bool(r))) => Ok(Bool(l == r)),
 
-                    (Ok(I32(_)), Ok(Bool(_))) => {
-                        Err(InterpretError("Cannot evaluate Arith = Bool".to_string()))
-                    }
+                    (Ok(Int(_)), Ok(Bool(_))) => error("Cannot evaluate Arith = Bool"),
 
-                    (Ok(Bool(_)), Ok(I32(_))) => {
-                        Err(InterpretError("Cannot evaluate Bool = Arith".to_string()))
-                    }
+                    (Ok(Bool(_)), Ok(Int(_))) => error("Cannot evaluate Bool = Arith"),
+
+                    (Err(e), _) | (_, Err(e)) => Err(e),
 
-                    _ => Err(InterpretError("Unexpected error".to_string())),
+                    _ => error("Unexpected error"),
                 }
             }
 
             Ast::LessEq { left, right } => {
-                let Ok(I32(left_inner)) = self.interpret_ast(left) else { return Err(InterpretError("LHS is not arithmetic".to_string())) };
-                let Ok(I32(right_inner)) = self.interpret_ast(right) else { return Err(InterpretError("RHS is not arithmetic".to_string())) };
+                let (left_inner, right_inner) = self.interpret_arithmetic(left, right)?;
 
                 Ok(Bool(left_inner <= right_inner))
             }
             Ast::And { left, right } => {
-                let Ok(Bool(left_inner)) = self.interpret_ast(left) else { return Err(InterpretError("LHS is not boolean".to_string())) };
-                let Ok(Bool(right_inner)) = self.interpret_ast(right) else { return Err(InterpretError("RHS is not boolean".to_string())) };
+                let Bool(left_inner) = self.interpret_ast(left)? else { return Err(InterpretError::new("LHS is not boolean".to_string(), left.span)) };
+                let Bool(right_inner) = self.interpret_ast(right)? else { return Err(InterpretError::new("RHS is not boolean".to_string(), right.span)) };
 
                 Ok(Bool(left_inner && right_inner))
             }
             Ast::Add { left, right } => {
-                let I32(left_inner) = self.interpret_ast(left)? else { return Err(InterpretError("Got boolean, not arithmetic.".to_string())) };
-                let I32(right_inner) = self.interpret_ast(right)? else { return Err(InterpretError("Got boolean, not arithmetic.".to_string())) };
-
-                Ok(I32(left_inner + right_inner))
+                let (left_inner, right_inner) = self.interpret_arithmetic(left, right)?;
+                self.check_overflow(&left_inner + &right_inner, ast)
             }
             Ast::Sub { left, right } => {
-                let Ok(I32(left_inner)) = self.interpret_ast(left) else { return Err(InterpretError("LHS is not arithmetic".to_string())) };
-                let Ok(I32(right_inner)) = self.interpret_ast(right) else { return Err(InterpretError("RHS is not arithmetic".to_string())) };
-
-                Ok(I32(left_inner - right_inner))
+                let (left_inner, right_inner) = self.interpret_arithmetic(left, right)?;
+                self.check_overflow(&left_inner - &right_inner, ast)
             }
             Ast::Mul { left, right } => {
-                let Ok(I32(left_inner)) = self.interpret_ast(left) else { return Err(InterpretError("LHS is not arithmetic".to_string())) };
-                let Ok(I32(right_inner)) = self.interpret_ast(right) else { return Err(InterpretError("RHS is not arithmetic".to_string())) };
+                let (left_inner, right_inner) = self.interpret_arithmetic(left, right)?;
+                self.check_overflow(&left_inner * &right_inner, ast)
+            }
+            Ast::Div { left, right } => {
+                let (quotient, _) = self.interpret_division(left, right)?;
+                self.check_overflow(quotient, ast)
+            }
+            Ast::Mod { left, right } => {
+                let (_, remainder) = self.interpret_division(left, right)?;
+                self.check_overflow(remainder, ast)
+            }
+            Ast::Literal(x) => Ok(Int(x.clone())),
+            Ast::Ident(i) => Ok(Int(self.context.get_variable(i))),
+        }
+    }
 
-                Ok(I32(left_inner * right_inner))
+    /// Binds a program to an identifier. Under static scoping, the definition
+    /// captures the definitions that exist now.
+    fn define(
+        &mut self,
+        ident: &str,
+        inputs: Vec<String>,
+        outputs: Vec<String>,
+        body: &Spanned<Ast>,
+    ) {
+        let environment = match self.scoping {
+            Scoping::Dynamic => None,
+            // Captured definitions are shared rather than copied, so this only
+            // copies the map itself.
+            Scoping::Static => Some(Rc::new(self.context.definitions.clone())),
+        };
+
+        self.context.add_definition(
+            ident.to_string(),
+            Definition {
+                inputs,
+                outputs,
+                body: body.clone(),
+                environment,
+            },
+        );
+    }
+
+    /// Runs a definition. Inputs are passed by value, and outputs by result:
+    /// the parameters are declared in a scope of their own while the body
+    /// runs, with the inputs set to the values of the arguments and the
+    /// outputs unset, and once the scope has ended, the outputs' final values
+    /// are assigned to the variables that were given for them. `span` is that
+    /// of the run.
+    fn call(
+        &mut self,
+        ident: &str,
+        inputs: &[Spanned<Ast>],
+        outputs: &[String],
+        span: Span,
+    ) -> Result<(), InterpretError> {
+        let Some(definition) = self.context.get_definition(ident).cloned() else {
+            return Err(InterpretError::new(
+                format!("Definition {} not defined yet", ident),
+                span,
+            ));
+        };
+
+        if definition.inputs.len() != inputs.len() || definition.outputs.len() != outputs.len() {
+            return Err(InterpretError {
+                kind: InterpretErrorKind::ArityMismatch,
+                ..InterpretError::new(
+                    format!(
+                        "Definition {ident} takes {} and {}, but was given {} and {}",
+                        plural(definition.inputs.len(), "input"),
+                        plural(definition.outputs.len(), "output"),
+                        plural(inputs.len(), "input"),
+                        plural(outputs.len(), "output"),
+                    ),
+                    span,
+                )
+            });
+        }
+
+        // The arguments are evaluated before any parameter is set, since they
+        // can mention variables with the same names.
+        let mut values = Vec::new();
+
+        for input in inputs {
+            let Int(value) = self.interpret_ast(input)? else {
+                return Err(InterpretError::new(
+                    "Argument is not arithmetic".to_string(),
+                    input.span,
+                ));
+            };
+            values.push(value);
+        }
+
+        self.context.start_scope();
+
+        for (parameter, value) in definition.inputs.iter().zip(values) {
+            self.context
+                .declare_variable(parameter.clone(), Some(value));
+        }
+
+        for parameter in &definition.outputs {
+            self.context.declare_variable(parameter.clone(), None);
+        }
+
+        // Under static scoping, the body runs with the definitions that it
+        // captured, and itself.
+        let outer = definition.environment.as_ref().map(|environment| {
+            let mut environment = Definitions::clone(environment);
+            environment.insert(ident.to_string(), Rc::clone(&definition));
+            std::mem::replace(&mut self.context.definitions, environment)
+        });
+
+        let result = self.interpret_ast(&definition.body);
+
+        if let Some(outer) = outer {
+            self.context.definitions = outer;
+        }
+
+        let results: Vec<_> = definition
+            .outputs
+            .iter()
+            .map(|output| self.context.get_variable(output))
+            .collect();
+
+        self.context.end_scope();
+        result?;
+
+        for (output, value) in outputs.iter().zip(results) {
+            self.context.set_variable(output.clone(), value);
+        }
+
+        Ok(())
+    }
+
+    /// Runs the declarations and body of a block with local variables, in the
+    /// scope that has been opened for it. Each declaration's value is worked
+    /// out before its variable is declared, so `var x := x + 1` uses the `x`
+    /// from outside the block.
+    fn interpret_block(
+        &mut self,
+        declarations: &[(String, Spanned<Ast>)],
+        body: &Spanned<Ast>,
+    ) -> Result<Value, InterpretError> {
+        for (ident, value) in declarations {
+            let Int(value_inner) = self.interpret_ast(value)? else {
+                return Err(InterpretError::new(
+                    format!("Bad RHS of declaration: {:?}", value.inner),
+                    value.span,
+                ));
+            };
+
+            self.context
+                .declare_variable(ident.clone(), Some(value_inner));
+        }
+
+        self.interpret_ast(body)
+    }
+
+    /// Checks the condition of an `assert` or `assume`, failing with an error
+    /// of the given kind, and the current state, if it doesn't hold.
+    fn check_condition(
+        &mut self,
+        cond: &Spanned<Ast>,
+        kind: InterpretErrorKind,
+        message: String,
+    ) -> Result<Value, InterpretError> {
+        match self.interpret_ast(cond)? {
+            Bool(true) => Ok(Unit),
+            Bool(false) => Err(InterpretError::with_state(
+                kind,
+                message,
+                cond.span,
+                self.context.state.clone(),
+            )),
+            _ => Err(InterpretError::new(
+                "Bad conditional".to_string(),
+                cond.span,
+            )),
+        }
+    }
+
+    /// Checks that a loop's invariant holds, if it has one and annotations are
+    /// being checked. `iterations` is how many iterations have finished.
+    fn check_invariant(
+        &mut self,
+        invariant: Option<&Spanned<Ast>>,
+        iterations: usize,
+    ) -> Result<(), InterpretError> {
+        let Some(invariant) = invariant.filter(|_| self.check_annotations) else {
+            return Ok(());
+        };
+
+        match self.interpret_ast(invariant)? {
+            Bool(true) => Ok(()),
+            Bool(false) => {
+                let when = match iterations {
+                    0 => "before the first iteration".to_string(),
+                    n => format!("after iteration {n}"),
+                };
+
+                Err(InterpretError::with_state(
+                    InterpretErrorKind::InvariantBroken { iterations },
+                    format!("Loop invariant doesn't hold {when}"),
+                    invariant.span,
+                    self.context.state.clone(),
+                ))
             }
-            Ast::Literal(x) => Ok(I32(*x)),
-            Ast::Ident(i) => Ok(I32(self.context.get_variable(i))),
+            _ => Err(InterpretError::new(
+                "Loop invariant is not boolean".to_string(),
+                invariant.span,
+            )),
+        }
+    }
+
+    /// Evaluates a loop's variant, if it has one and annotations are being
+    /// checked.
+    fn evaluate_variant(
+        &mut self,
+        variant: Option<&Spanned<Ast>>,
+    ) -> Result<Option<Integer>, InterpretError> {
+        let Some(variant) = variant.filter(|_| self.check_annotations) else {
+            return Ok(None);
+        };
+
+        let Int(value) = self.interpret_ast(variant)? else {
+            return Err(InterpretError::new(
+                "Loop variant is not arithmetic".to_string(),
+                variant.span,
+            ));
+        };
+
+        Ok(Some(value))
+    }
+
+    /// Interprets both operands of a binary arithmetic operator.
+    fn interpret_arithmetic(
+        &mut self,
+        left: &Spanned<Ast>,
+        right: &Spanned<Ast>,
+    ) -> Result<(Integer, Integer), InterpretError> {
+        let Int(left_inner) = self.interpret_ast(left)? else {
+            return Err(InterpretError::new(
+                "LHS is not arithmetic".to_string(),
+                left.span,
+            ));
+        };
+        let Int(right_inner) = self.interpret_ast(right)? else {
+            return Err(InterpretError::new(
+                "RHS is not arithmetic".to_string(),
+                right.span,
+            ));
+        };
+
+        Ok((left_inner, right_inner))
+    }
+
+    /// Interprets both operands of `/` or `%`, and divides them, rounding the
+    /// way the interpreter is set to.
+    fn interpret_division(
+        &mut self,
+        left: &Spanned<Ast>,
+        right: &Spanned<Ast>,
+    ) -> Result<(Integer, Integer), InterpretError> {
+        let (left_inner, right_inner) = self.interpret_arithmetic(left, right)?;
+
+        left_inner
+            .div_rem(&right_inner, self.rounding)
+            .ok_or_else(|| InterpretError::new("Division by zero".to_string(), right.span))
+    }
+
+    /// Checks that the result of some arithmetic is within the range of the
+    /// interpreter's integer type. The node is the operation that gave it.
+    fn check_overflow(
+        &self,
+        result: Integer,
+        node: &Spanned<Ast>,
+    ) -> Result<Value, InterpretError> {
+        if self.integer_type.contains(&result) {
+            Ok(Int(result))
+        } else {
+            Err(InterpretError::new(
+                format!(
+                    "Arithmetic overflow: {result} is out of range for {}",
+                    self.integer_type
+                ),
+                node.span,
+            ))
         }
     }
 }
+
+/// Writes a count of something, like "1 input" or "2 inputs".
+fn plural(count: usize, noun: &str) -> String {
+    match count {
+        1 => format!("{count} {noun}"),
+        _ => format!("{count} {noun}s"),
+    }
+}
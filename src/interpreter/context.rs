@@ -1,13 +1,37 @@
 use crate::ast::Ast;
 use crate::interpreter::state::State;
+use crate::lexer::Spanned;
+use crate::number::Integer;
 use std::collections::HashMap;
+use std::rc::Rc;
 
 /// A context for the interpreter. This can hold more than what just a
 /// State can -- it also holds definitions, which are programs that are
-/// bound to identifiers.
+/// bound to identifiers, and the scopes of local variables.
+#[derive(Default)]
 pub struct Context {
     pub state: State,
-    pub definitions: HashMap<String, Ast>,
+    pub definitions: Definitions,
+    /// The variables declared in each open scope, innermost last, along with
+    /// their values from before they were declared, if they were set.
+    scopes: Vec<Vec<(String, Option<Integer>)>>,
+}
+
+/// Definitions by name. They're shared, since a definition can be captured by
+/// many others under static scoping.
+pub type Definitions = HashMap<String, Rc<Definition>>;
+
+/// A program bound to an identifier, along with the names of its parameters.
+/// Definitions made like `W := [[...]]` have none.
+#[derive(Clone, Debug)]
+pub struct Definition {
+    pub inputs: Vec<String>,
+    pub outputs: Vec<String>,
+    pub body: Spanned<Ast>,
+    /// The definitions that existed when this one was defined, which its body
+    /// sees under static scoping. `None` under dynamic scoping, where the body
+    /// sees whatever is defined when it runs.
+    pub environment: Option<Rc<Definitions>>,
 }
 
 impl Context {
@@ -15,22 +39,74 @@ impl Context {
         Self {
             state: State::new(),
             definitions: HashMap::new(),
+            scopes: Vec::new(),
+        }
+    }
+
+    pub fn with_state(state: State) -> Self {
+        Self {
+            state,
+            definitions: HashMap::new(),
+            scopes: Vec::new(),
         }
     }
 
-    pub fn add_definition(&mut self, name: String, definition: Ast) {
-        self.definitions.insert(name, definition);
+    pub fn add_definition(&mut self, name: String, definition: Definition) {
+        self.definitions.insert(name, Rc::new(definition));
     }
 
-    pub fn get_definition(&self, name: &str) -> Option<&Ast> {
+    pub fn get_definition(&self, name: &str) -> Option<&Rc<Definition>> {
         self.definitions.get(name)
     }
 
-    pub fn set_variable(&mut self, name: String, value: i32) {
+    pub fn set_variable(&mut self, name: String, value: Integer) {
         self.state.set(name, value);
     }
 
-    pub fn get_variable(&self, name: &str) -> i32 {
+    pub fn get_variable(&self, name: &str) -> Integer {
         self.state.get(name)
     }
+
+    /// Opens a scope, in which variables can be declared with
+    /// [`Context::declare_variable`].
+    pub fn start_scope(&mut self) {
+        self.scopes.push(Vec::new());
+    }
+
+    /// Declares a variable in the innermost scope, setting it to the value,
+    /// or leaving it unset for `None`. Whatever it was before is put back when
+    /// the scope ends.
+    ///
+    /// # Panics
+    /// Panics if no scope is open.
+    pub fn declare_variable(&mut self, name: String, value: Option<Integer>) {
+        let scope = self.scopes.last_mut().expect("no scope is open");
+        let previous = self.state.remove(&name);
+
+        if let Some(value) = value {
+            self.state.set(name.clone(), value);
+        }
+
+        scope.push((name, previous));
+    }
+
+    /// Closes the innermost scope, putting its variables back to what they
+    /// were before they were declared.
+    ///
+    /// # Panics
+    /// Panics if no scope is open.
+    pub fn end_scope(&mut self) {
+        let scope = self.scopes.pop().expect("no scope is open");
+
+        // A variable declared twice in the scope ends up as it was before
+        // the first declaration.
+        for (name, previous) in scope.into_iter().rev() {
+            match previous {
+                Some(value) => self.state.set(name, value),
+                None => {
+                    self.state.remove(&name);
+                }
+            }
+        }
+    }
 }
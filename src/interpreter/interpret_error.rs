@@ -1,15 +1,77 @@
+use crate::interpreter::state::State;
+use crate::lexer::Span;
 use std::error::Error;
 use std::fmt::{Display, Formatter};
 
 /// Represents an error in the interpreting stage. Use this when you want to signify
 /// that, during interpreting, you found an undefined name or a bad type.
+///
+/// The span is that of the node which failed to run.
 #[derive(Debug)]
-pub struct InterpretError(pub String);
+pub struct InterpretError {
+    pub kind: InterpretErrorKind,
+    pub message: String,
+    pub span: Span,
+    /// The state when the error happened, for errors about a program's
+    /// annotations, where the values of the variables are what's needed to
+    /// see why.
+    pub state: Option<State>,
+}
+
+/// The kinds of [`InterpretError`], for code that needs to tell them apart.
+/// Loop annotations are only checked when the interpreter is set to, and
+/// their kinds record how many iterations of the loop had finished.
+///
+/// [`Infeasible`][InterpretErrorKind::Infeasible] isn't a fault in the
+/// program, but it still ends the run without a final state, so it's
+/// reported the same way.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum InterpretErrorKind {
+    /// An error in running the program, like an overflow or a bad type.
+    Runtime,
+    /// A loop invariant that doesn't hold, either before an iteration or once
+    /// the loop has finished.
+    InvariantBroken { iterations: usize },
+    /// A loop variant that's negative before an iteration, when it should
+    /// bound how many iterations are left.
+    VariantNegative { iterations: usize },
+    /// A loop variant that didn't decrease over an iteration.
+    VariantNotDecreasing { iterations: usize },
+    /// A run of a definition with a different number of inputs or outputs
+    /// than it has parameters.
+    ArityMismatch,
+    /// An `assert` whose condition doesn't hold.
+    AssertionFailed,
+    /// An `assume` whose condition doesn't hold, so the run isn't one that
+    /// the program is meant to have.
+    Infeasible,
+}
 
 impl Error for InterpretError {}
 
 impl Display for InterpretError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.0)
+        write!(f, "{}", self.message)
+    }
+}
+
+impl InterpretError {
+    pub fn new(message: String, span: Span) -> Self {
+        Self {
+            kind: InterpretErrorKind::Runtime,
+            message,
+            span,
+            state: None,
+        }
+    }
+
+    /// Creates an error that records the state it happened in.
+    pub fn with_state(kind: InterpretErrorKind, message: String, span: Span, state: State) -> Self {
+        Self {
+            kind,
+            message,
+            span,
+            state: Some(state),
+        }
     }
 }
@@ -0,0 +1,42 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// Which definitions a definition's body sees when it runs: the ones that
+/// exist when it runs, or the ones that existed when it was defined.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum Scoping {
+    /// Names in a body are looked up when it runs, so after `W := [[Y]]`,
+    /// redefining `Y` changes what `W` does.
+    #[default]
+    Dynamic,
+    /// A definition captures the definitions that existed when it was
+    /// defined, along with itself so that it can be recursive, and its body
+    /// only sees those.
+    Static,
+}
+
+impl Display for Scoping {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Scoping::Dynamic => "dynamic",
+            Scoping::Static => "static",
+        };
+
+        write!(f, "{name}")
+    }
+}
+
+/// Parses the names used by the `Display` impl: `dynamic` and `static`.
+impl FromStr for Scoping {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "dynamic" => Ok(Scoping::Dynamic),
+            "static" => Ok(Scoping::Static),
+            _ => Err(format!(
+                "Unknown scoping `{s}`; expected `dynamic` or `static`"
+            )),
+        }
+    }
+}
@@ -1,5 +1,9 @@
+use crate::lexer::{Lexer, Token};
+use crate::number::Integer;
 use std::collections::HashMap;
+use std::error::Error;
 use std::fmt::{Display, Formatter};
+use std::str::FromStr;
 
 /// A representation of the mathematical notion of state in While. It's a wrapper over a HashMap.
 ///
@@ -10,7 +14,7 @@ use std::fmt::{Display, Formatter};
 /// Explicit settings of values to 0 show up in the end Display, but gets on their own, don't.
 #[derive(Clone, Debug, Eq, PartialEq, Default)]
 pub struct State {
-    mappings: HashMap<String, i32>,
+    mappings: HashMap<String, Integer>,
 }
 
 impl State {
@@ -23,16 +27,29 @@ impl State {
     /// Retrieve a value from the State.
     ///
     /// If the key doesn't exist, 0 is returned.
-    pub fn get(&self, ident: &str) -> i32 {
+    pub fn get(&self, ident: &str) -> Integer {
         match self.mappings.get(ident) {
-            None => 0,
-            Some(&x) => x,
+            None => Integer::zero(),
+            Some(x) => x.clone(),
         }
     }
 
     /// Performs an assignment for a given ident.
-    pub fn set(&mut self, ident: String, val: i32) {
-        self.mappings.insert(ident, val);
+    pub fn set(&mut self, ident: String, val: impl Into<Integer>) {
+        self.mappings.insert(ident, val.into());
+    }
+
+    /// Removes a variable, so that it's as if it was never set. Returns its
+    /// value, if it was set.
+    pub fn remove(&mut self, ident: &str) -> Option<Integer> {
+        self.mappings.remove(ident)
+    }
+
+    /// Iterates over the explicitly set variables, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Integer)> {
+        self.mappings
+            .iter()
+            .map(|(ident, val)| (ident.as_str(), val))
     }
 }
 
@@ -53,3 +70,81 @@ impl Display for State {
         write!(f, "[{}]", output_str)
     }
 }
+
+/// Parses a state from the same syntax that its `Display` impl uses, such as
+/// `[x -> 1, y -> 2]`. Whitespace between parts is ignored.
+impl FromStr for State {
+    type Err = ParseStateError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let inner = s
+            .trim()
+            .strip_prefix('[')
+            .and_then(|s| s.strip_suffix(']'))
+            .ok_or_else(|| {
+                ParseStateError("A state must be enclosed in `[` and `]`".to_string())
+            })?;
+
+        let mut state = State::new();
+
+        if inner.trim().is_empty() {
+            return Ok(state);
+        }
+
+        for mapping in inner.split(',') {
+            let (ident, val) = parse_mapping(mapping)?;
+            state.set(ident, val);
+        }
+
+        Ok(state)
+    }
+}
+
+/// Parses a single `ident -> value` mapping, such as `x -> 1`.
+fn parse_mapping(mapping: &str) -> Result<(String, Integer), ParseStateError> {
+    let Some((ident, val)) = mapping.split_once("->") else {
+        return Err(ParseStateError(format!(
+            "Expected `->` in `{}`",
+            mapping.trim()
+        )));
+    };
+
+    parse_variable(ident, val)
+}
+
+/// Parses a variable's name and its value, each of which must be exactly one
+/// identifier or integer. Whitespace around them is ignored.
+///
+/// The identifier is checked with the lexer, so that a state can only mention
+/// variables that a program could.
+pub fn parse_variable(ident: &str, val: &str) -> Result<(String, Integer), ParseStateError> {
+    let ident = ident.trim();
+    let val = val.trim();
+
+    let mut tokens = Lexer::new(ident.chars());
+
+    if !matches!(
+        (tokens.next(), tokens.next()),
+        (Some(Ok(t)), None) if t.inner == Token::Identifier
+    ) {
+        return Err(ParseStateError(format!("`{ident}` is not a variable name")));
+    }
+
+    let val = val
+        .parse()
+        .map_err(|_| ParseStateError(format!("`{val}` is not a valid value for `{ident}`")))?;
+
+    Ok((ident.to_string(), val))
+}
+
+/// Represents an error when parsing a [`State`] from a string.
+#[derive(Debug, Eq, PartialEq)]
+pub struct ParseStateError(pub String);
+
+impl Error for ParseStateError {}
+
+impl Display for ParseStateError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
@@ -2,7 +2,8 @@
 //!
 //! This module provides a struct [`Lexer`] that takes an iterator over
 //! characters and is, itself, an [`Iterator`] that returns
-//! `Result<Spanned<Token>, ParseError>`.
+//! `Result<Spanned<Token>, ParseError>`. To lex everything in one go without
+//! stopping at the first error, use [`Lexer::lex_recovering`].
 //!
 //! This module also provides various utility structs such as some`Span` types
 //! and a [`LexError`] type.
@@ -13,13 +14,13 @@ mod token;
 use crate::lexer::token::Token::*;
 use std::iter::Peekable;
 
-pub use crate::lexer::lex_error::LexError;
+pub use crate::lexer::lex_error::{LexError, LexErrorKind};
 pub use crate::lexer::span::{Span, Spanned};
 pub use crate::lexer::token::Token;
 
 /// Lexes an incoming character stream. The resulting iterator contains no
 /// concrete information about literals or identifiers -- this can be accessed
-/// by using the `Span`s provided.
+/// by using the `Span`s provided, which are byte offsets into the source.
 pub struct Lexer<I>
 where
     I: Iterator<Item = char>,
@@ -50,9 +51,11 @@ where
 
     fn advance(&mut self) -> Option<char> {
         // Storing our own `current_index` rather than using an [`Enumerate`] is
-        // just a simpler and cleaner solution at the moment.
-        self.current_index += 1;
-        self.source.next()
+        // just a simpler and cleaner solution at the moment. Spans are in
+        // bytes, so that they can be used to slice the source.
+        let c = self.source.next()?;
+        self.current_index += c.len_utf8();
+        Some(c)
     }
 
     fn eat_ident(&mut self, start: char) -> Token {
@@ -63,7 +66,12 @@ where
 
         let mut ident_buffer = String::from(start);
 
-        while self.peek().is_some_and(|c| c.is_alphanumeric()) {
+        while self.peek().is_some_and(|&c| is_ident_continue(c)) {
+            ident_buffer.push(self.advance().unwrap());
+        }
+
+        // Primes can only come at the end, like the `x'` of Hoare logic.
+        while self.peek() == Some(&'\'') {
             ident_buffer.push(self.advance().unwrap());
         }
 
@@ -73,7 +81,13 @@ where
             "else" => Else,
             "while" => While,
             "do" => Do,
+            "inv" => Inv,
+            "var" => Var,
             "skip" => Skip,
+            "assert" => Assert,
+            "assume" => Assume,
+            "begin" => Begin,
+            "end" => End,
             "true" => True,
             "false" => False,
             _ => Identifier,
@@ -94,6 +108,59 @@ where
         Literal
     }
 
+    fn eat_line_comment(&mut self) -> Token {
+        // The line break itself isn't part of the comment, so that it can
+        // still end the statement.
+        while self.peek().is_some_and(|c| !matches!(c, '\r' | '\n')) {
+            self.advance();
+        }
+        LineComment
+    }
+
+    fn eat_block_comment(&mut self, start: usize) -> Result<Token, LexError> {
+        // The opening `/` has already been advanced past, so this eats the
+        // `*`. Block comments nest, so we keep track of how deep we are.
+        self.advance();
+        let mut depth = 1;
+
+        while depth > 0 {
+            match self.advance() {
+                Some('/') if self.peek() == Some(&'*') => {
+                    self.advance();
+                    depth += 1;
+                }
+                Some('*') if self.peek() == Some(&'/') => {
+                    self.advance();
+                    depth -= 1;
+                }
+                Some(_) => {}
+                None => {
+                    return Err(LexError::new(
+                        LexErrorKind::UnterminatedComment,
+                        "Unterminated block comment".to_string(),
+                        Span(start, start + 2),
+                    ))
+                }
+            }
+        }
+
+        Ok(BlockComment)
+    }
+
+    /// Describes why the token starting with `c` couldn't be lexed.
+    fn unknown_error(c: char, span: Span) -> LexError {
+        let incomplete = |message: &str| {
+            LexError::new(LexErrorKind::IncompleteOperator, message.to_string(), span)
+        };
+
+        match c {
+            ':' => incomplete("`:` must be followed by `=`"),
+            '[' => incomplete("single `[`; did you mean `[[`?"),
+            ']' => incomplete("single `]`; did you mean `]]`?"),
+            _ => LexError::unknown_characters(c.to_string(), span),
+        }
+    }
+
     fn eat_linebreaks(&mut self) -> Token {
         while let Some('\n' | '\r') = self.peek() {
             self.advance();
@@ -102,6 +169,74 @@ where
     }
 }
 
+impl<I> Lexer<I>
+where
+    I: Iterator<Item = char>,
+{
+    /// Lexes the whole character stream without stopping at the first error.
+    ///
+    /// Returns every token, with an `Unknown` token standing in for each part
+    /// of the source that couldn't be lexed, along with every error. Runs of
+    /// unknown characters are merged into one token and one error.
+    pub fn lex_recovering(mut self) -> (Vec<Spanned<Token>>, Vec<LexError>) {
+        let mut tokens: Vec<Spanned<Token>> = Vec::new();
+        let mut errors: Vec<LexError> = Vec::new();
+
+        while let Some(result) = self.next() {
+            let error = match result {
+                Ok(token) => {
+                    tokens.push(token);
+                    continue;
+                }
+                Err(error) => error,
+            };
+
+            if let (
+                Some(LexError {
+                    kind: LexErrorKind::UnknownCharacters(previous),
+                    span: previous_span,
+                    ..
+                }),
+                LexErrorKind::UnknownCharacters(current),
+            ) = (errors.last(), &error.kind)
+            {
+                if previous_span.1 == error.span.0 {
+                    let span = Span(previous_span.0, error.span.1);
+                    let merged = LexError::unknown_characters(previous.clone() + current, span);
+
+                    *errors.last_mut().unwrap() = merged;
+                    tokens.last_mut().unwrap().span = span;
+                    continue;
+                }
+            }
+
+            // An unterminated comment's error points at its `/*`, but the
+            // comment runs to the end of the source, and so does its token, so
+            // that no source is lost.
+            let span = match error.kind {
+                LexErrorKind::UnterminatedComment => Span(error.span.0, self.current_index),
+                _ => error.span,
+            };
+
+            tokens.push(Spanned::new(Unknown, span));
+            errors.push(error);
+        }
+
+        (tokens, errors)
+    }
+}
+
+/// Whether an identifier can start with `c`: any letter, or an underscore.
+fn is_ident_start(c: char) -> bool {
+    c.is_alphabetic() || c == '_'
+}
+
+/// Whether an identifier can continue with `c`: any letter or digit, or an
+/// underscore.
+fn is_ident_continue(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
 impl<I> Iterator for Lexer<I>
 where
     I: Iterator<Item = char>,
@@ -111,21 +246,38 @@ where
     fn next(&mut self) -> Option<Self::Item> {
         let start = self.current_index;
 
-        let c = match self.advance() {
-            None => return None,
-            Some(t) => t,
-        };
+        let c = self.advance()?;
 
         let token = match c {
             '(' => LeftParen,
             ')' => RightParen,
+            '{' => LeftBrace,
+            '}' => RightBrace,
+            ',' => Comma,
 
             '+' => Add,
             '-' => Subtract,
             '*' => Multiply,
 
-            '=' => Equal,
+            '/' => match self.peek() {
+                Some('/') => self.eat_line_comment(),
+                Some('*') => match self.eat_block_comment(start) {
+                    Ok(t) => t,
+                    Err(e) => return Some(Err(e)),
+                },
+                _ => Divide,
+            },
+            '%' => Modulo,
+
+            '=' => match self.peek() {
+                Some('>') => {
+                    self.advance();
+                    Implies
+                }
+                _ => Equal,
+            },
             '&' => And,
+            '|' => Or,
 
             '[' => match self.peek() {
                 Some('[') => {
@@ -176,9 +328,23 @@ where
                 }
             }
 
+            // The handbook's notation, which are alternative forms of the
+            // ASCII operators above.
+            '−' => Subtract,
+            '∧' => And,
+            '∨' => Or,
+            '⇒' => Implies,
+            '¬' => Not,
+            '≤' => LessEqual,
+            '≥' => GreaterEqual,
+            '≠' => NotEqual,
+            '≜' => Assign,
+            '⟦' => LeftSemantic,
+            '⟧' => RightSemantic,
+
             '0'..='9' => self.eat_numbers(),
 
-            'a'..='z' | 'A'..='Z' => self.eat_ident(c),
+            c if is_ident_start(c) => self.eat_ident(c),
 
             ' ' | '\t' => self.eat_whitespaces(),
 
@@ -190,7 +356,7 @@ where
         let span = Span(start, self.current_index);
 
         let output = match token {
-            Unknown => Err(LexError::new("Unknown token".to_string(), span)),
+            Unknown => Err(Self::unknown_error(c, span)),
             t => Ok(Spanned::new(t, span)),
         };
 
@@ -7,13 +7,41 @@ use std::fmt::{Display, Formatter};
 /// error with the character stream.
 #[derive(Debug)]
 pub struct LexError {
+    pub kind: LexErrorKind,
     pub message: String,
     pub span: Span,
 }
 
+/// The kinds of [`LexError`], for code that needs to tell them apart.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum LexErrorKind {
+    /// Characters that can't start any token. Holds the characters themselves.
+    UnknownCharacters(String),
+    /// The start of a multi-character operator, without the rest of it, like a
+    /// `:` that isn't followed by `=`.
+    IncompleteOperator,
+    /// A block comment that is never closed.
+    UnterminatedComment,
+}
+
 impl LexError {
-    pub fn new(message: String, span: Span) -> Self {
-        Self { message, span }
+    pub fn new(kind: LexErrorKind, message: String, span: Span) -> Self {
+        Self {
+            kind,
+            message,
+            span,
+        }
+    }
+
+    /// Creates an error for a run of unknown characters.
+    pub fn unknown_characters(characters: String, span: Span) -> Self {
+        let message = if characters.chars().count() == 1 {
+            format!("Unknown character `{characters}`")
+        } else {
+            format!("Unknown characters `{characters}`")
+        };
+
+        Self::new(LexErrorKind::UnknownCharacters(characters), message, span)
     }
 }
 
@@ -2,8 +2,16 @@
 //!
 //! Use `Spanned<T>` to associate a `T` with its span, and use `Span` for code
 //! that doesn't need the `T`.
+//!
+//! Spans are always measured in bytes, so they can be used to slice the source
+//! directly. To convert them into char offsets, or into the line and UTF-16
+//! column that editors use, see [`SourceNavigator`][source_navigator].
+//!
+//! [source_navigator]: crate::utils::SourceNavigator
+
+use std::fmt::{Debug, Formatter};
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone)]
 pub struct Spanned<T> {
     pub inner: T,
     pub span: Span,
@@ -15,5 +23,18 @@ impl<T> Spanned<T> {
     }
 }
 
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+/// Writes the inner value followed by the span, like `Skip@0..4`, to keep
+/// debug output of large trees readable.
+impl<T: Debug> Debug for Spanned<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.inner.fmt(f)?;
+        write!(f, "@{}..{}", self.span.0, self.span.1)
+    }
+}
+
+/// A half-open range of byte offsets into the source, from the first byte of
+/// the spanned text up to (but not including) the byte after it.
+///
+/// The default span is the empty span at the start of the source.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
 pub struct Span(pub usize, pub usize);
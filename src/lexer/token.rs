@@ -11,11 +11,15 @@ pub enum Token {
     RightParen,
     LeftSemantic,
     RightSemantic,
+    LeftBrace,
+    RightBrace,
 
     // Arithmetic operators
     Add,
     Subtract,
     Multiply,
+    Divide,
+    Modulo,
 
     // Literals
     Literal,
@@ -32,6 +36,8 @@ pub enum Token {
     GreaterThan,
     Not,
     And,
+    Or,
+    Implies,
 
     // Statement identifiers
     Assign,
@@ -40,11 +46,79 @@ pub enum Token {
     Else,
     While,
     Do,
+    Inv,
+    Var,
     Skip,
+    Assert,
+    Assume,
+    Begin,
+    End,
 
     // Miscellaneous symbols
     Whitespace,
     LineBreak,
+    LineComment,
+    BlockComment,
     Unknown,
     Semicolon,
+    Comma,
+}
+
+impl Token {
+    /// The ASCII spelling of this token in source code, if it's always spelled
+    /// the same way.
+    pub fn spelling(&self) -> Option<&'static str> {
+        let spelling = match self {
+            Token::LeftParen => "(",
+            Token::RightParen => ")",
+            Token::LeftSemantic => "[[",
+            Token::RightSemantic => "]]",
+            Token::LeftBrace => "{",
+            Token::RightBrace => "}",
+            Token::Add => "+",
+            Token::Subtract => "-",
+            Token::Multiply => "*",
+            Token::Divide => "/",
+            Token::Modulo => "%",
+            Token::True => "true",
+            Token::False => "false",
+            Token::Equal => "=",
+            Token::NotEqual => "!=",
+            Token::LessEqual => "<=",
+            Token::LessThan => "<",
+            Token::GreaterEqual => ">=",
+            Token::GreaterThan => ">",
+            Token::Not => "!",
+            Token::And => "&",
+            Token::Or => "|",
+            Token::Implies => "=>",
+            Token::Assign => ":=",
+            Token::If => "if",
+            Token::Then => "then",
+            Token::Else => "else",
+            Token::While => "while",
+            Token::Do => "do",
+            Token::Inv => "inv",
+            Token::Var => "var",
+            Token::Skip => "skip",
+            Token::Assert => "assert",
+            Token::Assume => "assume",
+            Token::Begin => "begin",
+            Token::End => "end",
+            Token::Semicolon => ";",
+            Token::Comma => ",",
+            _ => return None,
+        };
+
+        Some(spelling)
+    }
+
+    /// Whether this token is trivia: something that has no meaning to the
+    /// parser, like whitespace and comments.
+    pub fn is_trivia(&self) -> bool {
+        matches!(
+            self,
+            Token::Whitespace | Token::LineBreak | Token::LineComment | Token::BlockComment
+        )
+    }
 }
@@ -4,12 +4,17 @@
 //! inspired by a language described by C. A. Hoare in
 //! [An Axiomatic Basis for Computer Programming](https://dl.acm.org/doi/10.1145/363235.363259).
 pub mod ast;
+pub mod cst;
+pub mod formatter;
 pub mod interpreter;
 pub mod lexer;
+pub mod lint;
+pub mod number;
 pub mod parser;
+pub mod repl;
 mod source_navigator;
 
 pub mod utils {
     use crate::source_navigator;
-    pub use source_navigator::SourceNavigator;
+    pub use source_navigator::{FilePos2d, SourceNavigator};
 }
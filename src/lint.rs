@@ -0,0 +1,156 @@
+//! A module for lints: checks that point out code that is valid, but that
+//! might not be what the author meant, or that could be written better.
+//!
+//! Lints never stop a program from running, so they are opt-in. Each lint is
+//! a function that returns every [`Lint`] it found.
+
+use crate::ast::Ast;
+use crate::comp_stmt;
+use crate::lexer::{Span, Spanned, Token};
+use std::fmt::{Display, Formatter};
+
+/// A warning about a specific part of the source.
+#[derive(Debug, Eq, PartialEq)]
+pub struct Lint {
+    pub message: String,
+    pub span: Span,
+    /// How the code could be written instead, when there's more to say than
+    /// the message.
+    pub note: Option<String>,
+}
+
+impl Lint {
+    pub fn new(message: String, span: Span) -> Self {
+        Self {
+            message,
+            span,
+            note: None,
+        }
+    }
+
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.note = Some(note.into());
+        self
+    }
+}
+
+impl Display for Lint {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+/// Reports every operator written in its non-ASCII form, like the handbook's
+/// `≤` or `−`, along with the ASCII form it could be replaced by.
+pub fn non_ascii_operators(source: &str, tokens: &[Spanned<Token>]) -> Vec<Lint> {
+    tokens
+        .iter()
+        .filter_map(|&Spanned { inner, span }| {
+            let ascii_form = inner.spelling()?;
+            let text = &source[span.0..span.1];
+
+            (!text.is_ascii()).then(|| {
+                Lint::new(
+                    format!("`{text}` is the non-ASCII form of `{ascii_form}`"),
+                    span,
+                )
+            })
+        })
+        .collect()
+}
+
+/// Reports every `if` and `while` whose last block is more than one statement,
+/// without brackets around them. The last block takes every statement after
+/// it, so in `while b do W; S`, `S` is part of the loop, which is easily
+/// missed. The note suggests `(while b do W); S` instead.
+///
+/// Blocks in brackets, or in the delimited block style, aren't reported, and
+/// neither are blocks of a single statement, or an `if` or `while` that is in
+/// brackets itself.
+pub fn greedy_blocks(ast: &Spanned<Ast>) -> Vec<Lint> {
+    let mut lints = Vec::new();
+    find_greedy_blocks(ast, &mut lints);
+    lints
+}
+
+fn find_greedy_blocks(ast: &Spanned<Ast>, lints: &mut Vec<Lint>) {
+    match &ast.inner {
+        Ast::Comp { first, second } => {
+            find_greedy_blocks(first, lints);
+            find_greedy_blocks(second, lints);
+        }
+        Ast::Ass { value: body, .. } | Ast::Procedure { body, .. } => {
+            find_greedy_blocks(body, lints)
+        }
+        Ast::Triple { stmt: body, .. } | Ast::Block { body, .. } => find_greedy_blocks(body, lints),
+        Ast::If {
+            true_path,
+            false_path,
+            ..
+        } => {
+            // The implicit `else skip` of a short `if` is empty, so the `then`
+            // block is the last one.
+            let last_block = match false_path.span {
+                Span(start, end) if start == end => "then",
+                _ => "else",
+            };
+
+            lints.extend(greedy_block(ast, "if", last_block));
+            find_greedy_blocks(true_path, lints);
+            find_greedy_blocks(false_path, lints);
+        }
+        Ast::While { body, .. } => {
+            lints.extend(greedy_block(ast, "while", "do"));
+            find_greedy_blocks(body, lints);
+        }
+        _ => {}
+    }
+}
+
+/// Makes a lint for an `if` or `while` if its last block, which comes after
+/// the `last_block` keyword, is greedy.
+fn greedy_block(stmt: &Spanned<Ast>, keyword: &str, last_block: &str) -> Option<Lint> {
+    let mut suggestion = stmt.clone();
+
+    let block = match &mut suggestion.inner {
+        Ast::If { true_path, .. } if last_block == "then" => true_path,
+        Ast::If { false_path, .. } => false_path,
+        Ast::While { body, .. } => body,
+        _ => return None,
+    };
+
+    // An `if` or `while` in brackets of its own has a span that runs to the
+    // closing bracket, past its last block, which clearly ends there too.
+    if stmt.span.1 > block.span.1 {
+        return None;
+    }
+
+    let statements = unbracketed_statements(block);
+    let (first, rest) = statements.split_first()?;
+    let (second, rest) = rest.split_first()?;
+
+    // Only the first statement is left in the block, and the rest follow the
+    // whole `if` or `while`.
+    let after = rest.iter().fold(second.clone(), |after, stmt| {
+        Spanned::new(comp_stmt!(after, stmt.clone()), Span::default())
+    });
+    **block = first.clone();
+    let suggestion = comp_stmt!(suggestion, after);
+
+    let message = format!("Every statement after `{last_block}` is part of this `{keyword}`");
+    let note = format!("if only the first of them is meant to be, write `{suggestion}`");
+    Some(Lint::new(message, stmt.span).with_note(note))
+}
+
+/// The statements of a block, in order, unless it's in brackets. A bracketed
+/// block's span includes its brackets, so it's wider than its statements.
+fn unbracketed_statements(block: &Spanned<Ast>) -> Vec<Spanned<Ast>> {
+    match &block.inner {
+        Ast::Comp { first, second } if block.span == Span(first.span.0, second.span.1) => {
+            let mut statements = unbracketed_statements(first);
+            statements.push((**second).clone());
+            statements
+        }
+        _ => vec![block.clone()],
+    }
+}
@@ -1,115 +1,435 @@
-use std::fs::File;
-use std::io::Read;
-use std::process::exit;
-use while_tools::interpreter::Interpreter;
+//! The `while` command-line tool.
+//!
+//! Runs a While program (or just part of the pipeline) on a file, or on stdin
+//! when no file, or `-`, is given. The exit code tells scripts which stage of
+//! the pipeline failed.
+use std::env;
+use std::fs;
+use std::io::{self, BufRead, Read, Write};
+use std::process::ExitCode;
 
-use while_tools::lexer::Lexer;
-use while_tools::parser::Parser;
+use while_tools::ast::Ast;
+use while_tools::formatter;
+use while_tools::interpreter::interpret_error::InterpretErrorKind;
+use while_tools::interpreter::scoping::Scoping;
+use while_tools::interpreter::state::{self, State};
+use while_tools::interpreter::Interpreter;
+use while_tools::lexer::{Lexer, Span, Spanned, Token};
+use while_tools::lint;
+use while_tools::number::{IntegerType, Rounding};
+use while_tools::parser::{BlockStyle, Parser};
+use while_tools::repl::{Repl, Response};
 use while_tools::utils::SourceNavigator;
 
-fn main() {
-    // This is a basic example showing the pipeline as to how the source is
-    // processed.
+const USAGE: &str = "\
+Usage: while <command> [options] [file]
+
+Commands:
+    run      Lex, parse and interpret a program, then print the final state
+    lex      Print the token stream of a program
+    parse    Print the AST of a program
+    check    Lex and parse a program, reporting any errors
+    lint     Lex and parse a program, and warn about style problems
+    fmt      Print a program formatted, one statement per line
+    repl     Start an interactive session, after running the file if given
+
+Options:
+    --int <type>            Use `i32` (the default), `i64` or `big` integers
+    --blocks <style>        Parse `if` and `while` bodies in the `greedy` (the
+                            default) or `delimited` block style
+
+Options for `fmt`:
+    --to <style>            Write bodies in the `greedy` or `delimited` block
+                            style, rather than the style they were parsed in
+
+Options for `run` and `repl`:
+    --rounding <rounding>   Use `truncated` (the default) or `euclidean` division
+    --scoping <scoping>     Use `dynamic` (the default) or `static` scoping for
+                            definitions
+    --check-annotations     Check loop invariants and variants as the program runs
+    --set <name>=<value>    Set a variable in the initial state. Can be repeated
+    --state <state>         Set the initial state, written like `[x -> 1, y -> 2]`
+
+Apart from `repl`, if no file is given, or the file is `-`, the program is read
+from stdin.";
+
+// Exit codes, so that scripts can tell which stage of the pipeline failed.
+const EXIT_LEX_ERROR: u8 = 1;
+const EXIT_PARSE_ERROR: u8 = 2;
+const EXIT_INTERPRET_ERROR: u8 = 3;
+const EXIT_USAGE: u8 = 64;
+const EXIT_IO_ERROR: u8 = 74;
+
+#[derive(Copy, Clone, Debug)]
+enum Command {
+    Run,
+    Lex,
+    Parse,
+    Check,
+    Lint,
+    Fmt,
+    Repl,
+}
+
+/// The parsed command-line arguments.
+struct Options {
+    command: Command,
+    path: Option<String>,
+    initial_state: State,
+    integer_type: IntegerType,
+    rounding: Rounding,
+    check_annotations: bool,
+    scoping: Scoping,
+    block_style: BlockStyle,
+    /// The block style that `fmt` writes in, if it isn't `block_style`.
+    format_style: Option<BlockStyle>,
+}
 
-    // Getting a handle on the file.
-    println!("Opening file...");
+/// The source being worked on, along with a name to refer to it by in
+/// diagnostics.
+struct Input {
+    name: String,
+    source: String,
+}
 
-    let file_path = "examples/main.while";
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
 
-    let mut file = match File::open(file_path) {
-        Ok(f) => {
-            println!("File opened successfully.");
-            f
+    let options = match parse_args(&args) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("error: {message}\n\n{USAGE}");
+            return ExitCode::from(EXIT_USAGE);
         }
-        Err(e) => {
-            println!("{e}");
-            exit(0);
+    };
+
+    if let Command::Repl = options.command {
+        return repl(&options);
+    }
+
+    let input = match read_input(options.path.as_deref()) {
+        Ok(input) => input,
+        Err(message) => {
+            eprintln!("error: {message}");
+            return ExitCode::from(EXIT_IO_ERROR);
         }
     };
 
-    // Reading the file into a string, assuming valid UTF-8
+    match run_command(&options, &input) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(code) => ExitCode::from(code),
+    }
+}
+
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut args = args.iter();
+
+    let command = match args.next().map(String::as_str) {
+        Some("run") => Command::Run,
+        Some("lex") => Command::Lex,
+        Some("parse") => Command::Parse,
+        Some("check") => Command::Check,
+        Some("lint") => Command::Lint,
+        Some("fmt") => Command::Fmt,
+        Some("repl") => Command::Repl,
+        Some(other) => return Err(format!("unknown command `{other}`")),
+        None => return Err("no command given".to_string()),
+    };
 
-    let mut source_string = String::new();
+    let mut path = None;
+    let mut initial_state = State::new();
+    let mut integer_type = IntegerType::default();
+    let mut rounding = Rounding::default();
+    let mut check_annotations = false;
+    let mut scoping = Scoping::default();
+    let mut block_style = BlockStyle::default();
+    let mut format_style = None;
 
-    println!("\nReading file...");
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--set" => {
+                let assignment = args
+                    .next()
+                    .ok_or("`--set` needs a `<name>=<value>` argument")?;
 
-    match file.read_to_string(&mut source_string) {
-        Ok(b) => {
-            println!("File read successfully ({b} bytes).")
+                let Some((name, value)) = assignment.split_once('=') else {
+                    return Err(format!("`--set {assignment}` is missing an `=`"));
+                };
+
+                let (ident, val) = state::parse_variable(name, value)
+                    .map_err(|e| format!("bad `--set {assignment}`: {e}"))?;
+
+                initial_state.set(ident, val);
+            }
+
+            "--state" => {
+                let state: State = args
+                    .next()
+                    .ok_or("`--state` needs a state argument")?
+                    .parse()
+                    .map_err(|e| format!("bad `--state`: {e}"))?;
+
+                for (ident, val) in state.iter() {
+                    initial_state.set(ident.to_string(), val.clone());
+                }
+            }
+
+            "--int" => {
+                integer_type = args
+                    .next()
+                    .ok_or("`--int` needs one of `i32`, `i64` or `big`")?
+                    .parse()?;
+            }
+
+            "--rounding" => {
+                rounding = args
+                    .next()
+                    .ok_or("`--rounding` needs one of `truncated` or `euclidean`")?
+                    .parse()?;
+            }
+
+            "--scoping" => {
+                scoping = args
+                    .next()
+                    .ok_or("`--scoping` needs one of `dynamic` or `static`")?
+                    .parse()?;
+            }
+
+            "--check-annotations" => check_annotations = true,
+
+            "--blocks" => {
+                block_style = args
+                    .next()
+                    .ok_or("`--blocks` needs one of `greedy` or `delimited`")?
+                    .parse()?;
+            }
+
+            "--to" => {
+                let style = args
+                    .next()
+                    .ok_or("`--to` needs one of `greedy` or `delimited`")?
+                    .parse()?;
+                format_style = Some(style);
+            }
+
+            option if option.starts_with("--") => return Err(format!("unknown option `{option}`")),
+
+            _ if path.is_none() => path = Some(arg.clone()),
+
+            _ => return Err("too many arguments".to_string()),
+        }
+    }
+
+    Ok(Options {
+        command,
+        path,
+        initial_state,
+        integer_type,
+        rounding,
+        check_annotations,
+        scoping,
+        block_style,
+        format_style,
+    })
+}
+
+fn read_input(path: Option<&str>) -> Result<Input, String> {
+    match path {
+        None | Some("-") => {
+            let mut source = String::new();
+            io::stdin()
+                .read_to_string(&mut source)
+                .map_err(|e| format!("failed reading stdin: {e}"))?;
+
+            Ok(Input {
+                name: "<stdin>".to_string(),
+                source,
+            })
         }
-        Err(e) => {
-            println!("Failed reading `{file_path}` to string: {e}");
-            exit(0)
+
+        Some(path) => {
+            let source =
+                fs::read_to_string(path).map_err(|e| format!("failed reading `{path}`: {e}"))?;
+
+            Ok(Input {
+                name: path.to_string(),
+                source,
+            })
         }
     }
+}
+
+fn run_command(options: &Options, input: &Input) -> Result<(), u8> {
+    let (tokens, lexed_ok) = lex(input);
 
-    // First is the lexing. `Lexer` itself is an `Iterator`, so we can collect
-    // on it directly. But we need to check for, and handle, `LexError`s.
-    // The below method only handles the first error that ever appears in the
-    // stream.
-    //
-    // (Also note that this way, of collecting into a Vec, means it's less than
-    // ideal for larger files -- we have to lex the entire source and allocate
-    // for it, and then pass that on. In making the whole pipeline work on
-    // iterators, we'd have to change this later on).
-    //
-    // In short, we need fallible iterators.
-    println!("\nLexing...");
-
-    let tokens = Lexer::new(source_string.chars());
-
-    let result: Vec<_> = match tokens.collect::<Result<Vec<_>, _>>() {
-        Ok(v) => {
-            println!("Lexed successfully ({} tokens).", v.len());
-            v
+    if let Command::Lex = options.command {
+        for &Spanned { inner, span } in &tokens {
+            let text = &input.source[span.0..span.1];
+            println!(
+                "{:>4}..{:<4} {:<14} {text:?}",
+                span.0,
+                span.1,
+                format!("{inner:?}")
+            );
         }
-        Err(e) => {
-            println!("{e}");
-            let sn = SourceNavigator::new(&source_string);
-            let errored_line = sn.get_annotated_span(e.span);
+    }
+
+    if !lexed_ok {
+        return Err(EXIT_LEX_ERROR);
+    } else if let Command::Lex = options.command {
+        return Ok(());
+    }
+
+    let ast = parse(options, input, &tokens)?;
 
-            println!("\n{errored_line}");
-            exit(0);
+    match options.command {
+        Command::Parse => println!("{ast:#?}"),
+        Command::Check => println!("{}: ok", input.name),
+        Command::Fmt => {
+            let style = options.format_style.unwrap_or(options.block_style);
+            print!("{}", formatter::format(&ast.inner, style));
         }
-    };
+        Command::Lint => {
+            let mut lints = lint::non_ascii_operators(&input.source, &tokens);
+            lints.extend(lint::greedy_blocks(&ast));
+            lints.sort_by_key(|lint| lint.span.0);
+
+            for lint in &lints {
+                report(input, "warning", &lint.message, lint.span);
+
+                if let Some(note) = &lint.note {
+                    eprintln!(" = note: {note}");
+                }
+            }
+            println!("{}: {} warning(s)", input.name, lints.len());
+        }
+        Command::Run => {
+            let mut interpreter = Interpreter::with_state(ast, options.initial_state.clone());
+            interpreter.set_integer_type(options.integer_type);
+            interpreter.set_rounding(options.rounding);
+            interpreter.set_check_annotations(options.check_annotations);
+            interpreter.set_scoping(options.scoping);
+
+            match interpreter.interpret() {
+                Ok(state) => println!("{state}"),
+                // A failed `assume` means that the run isn't one the program
+                // is meant to have, which isn't an error.
+                Err(e) if e.kind == InterpretErrorKind::Infeasible => println!("infeasible"),
+                Err(e) => {
+                    report(input, "error", &e.message, e.span);
 
-    // Now that we have an errorless stream of tokens, we parse. The parser needs
-    // access to the source so that it can extract identifier names and literal
-    // values and add them into the AST.
-    println!("\nParsing...");
-    let mut parser = Parser::new(&source_string, result);
-
-    // let ast = match parser.parse() {
-    //     Ok(ast) => ast,
-    //     Err(msg) => {
-    //         println!("Error: {msg}");
-    //         exit(0)
-    //     }
-    // };
-
-    let ast = match parser.parse() {
-        Ok(ast) => ast,
-        Err(e) => {
-            println!("{e}");
-
-            let sn = SourceNavigator::new(&source_string);
-            let errored_line = sn.get_annotated_span(e.span);
-
-            println!("\n{errored_line}");
-            exit(0)
+                    if let Some(state) = &e.state {
+                        eprintln!(" = note: the state was {state}");
+                    }
+                    return Err(EXIT_INTERPRET_ERROR);
+                }
+            }
         }
+        Command::Lex | Command::Repl => unreachable!(),
+    }
+
+    Ok(())
+}
+
+fn repl(options: &Options) -> ExitCode {
+    let mut repl = Repl::with_state(options.initial_state.clone());
+    repl.set_integer_type(options.integer_type);
+    repl.set_rounding(options.rounding);
+    repl.set_check_annotations(options.check_annotations);
+    repl.set_scoping(options.scoping);
+    repl.set_block_style(options.block_style);
+
+    let mut respond = |input: &str| match repl.handle(input) {
+        Response::Output(output) if output.is_empty() => true,
+        Response::Output(output) => {
+            println!("{output}");
+            true
+        }
+        Response::Error(message) => {
+            eprintln!("error: {message}");
+            true
+        }
+        Response::Quit => false,
     };
 
-    println!("Parsed.");
+    if let Some(path) = &options.path {
+        respond(&format!(":load {path}"));
+    }
+
+    println!("While REPL. Type `:help` for a list of commands.");
+
+    let mut lines = io::stdin().lock().lines();
 
-    // The next step is to interpret this AST.
+    loop {
+        print!("> ");
+        let _ = io::stdout().flush();
 
-    println!("\nInterpreting...");
+        let Some(Ok(line)) = lines.next() else {
+            // End of input, so finish the prompt's line before leaving.
+            println!();
+            break;
+        };
 
-    let result = Interpreter::new(ast).interpret();
+        if !respond(&line) {
+            break;
+        }
+    }
+
+    ExitCode::SUCCESS
+}
+
+/// Lexes the whole input, reporting every error along the way. Also returns
+/// whether lexing was successful.
+fn lex(input: &Input) -> (Vec<Spanned<Token>>, bool) {
+    let (tokens, errors) = Lexer::new(input.source.chars()).lex_recovering();
 
-    match result {
-        Ok(state) => println!("Interpreted. {state}"),
-        Err(e) => println!("{e}"),
+    for e in &errors {
+        report(input, "error", &e.message, e.span);
     }
+
+    (tokens, errors.is_empty())
+}
+
+/// Parses the whole input, reporting every error along the way.
+fn parse(options: &Options, input: &Input, tokens: &[Spanned<Token>]) -> Result<Spanned<Ast>, u8> {
+    let mut parser = Parser::new(&input.source, tokens.iter().copied());
+    parser.set_integer_type(options.integer_type);
+    parser.set_block_style(options.block_style);
+
+    let (ast, errors) = parser.parse_recovering();
+
+    for e in &errors {
+        report(input, "error", &e.message, e.span);
+
+        if let Some(note) = &e.note {
+            eprintln!(" = note: {note}");
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(ast)
+    } else {
+        Err(EXIT_PARSE_ERROR)
+    }
+}
+
+/// Prints a diagnostic to stderr, along with the line of source it occurred on.
+fn report(input: &Input, severity: &str, message: &str, span: Span) {
+    eprintln!("{severity}: {message}");
+
+    if input.source.is_empty() {
+        eprintln!(" --> {}", input.name);
+        return;
+    }
+
+    let sn = SourceNavigator::new(&input.source);
+    let position = sn.get_position(span.0);
+
+    eprintln!(
+        " --> {}:{}:{}",
+        input.name,
+        position.row + 1,
+        position.col + 1
+    );
+    eprintln!("{}", sn.get_annotated_span(span));
 }
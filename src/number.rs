@@ -0,0 +1,465 @@
+//! A module for the integers that While programs work with.
+//!
+//! Every integer is stored as an arbitrary-precision [`Integer`]. Which values
+//! are actually allowed is decided by an [`IntegerType`]: the parser uses it to
+//! reject literals that are out of range, and the interpreter uses it to catch
+//! arithmetic that overflows.
+
+use std::cmp::Ordering;
+use std::error::Error;
+use std::fmt::{Debug, Display, Formatter};
+use std::ops::{Add, Mul, Neg, Sub};
+use std::str::FromStr;
+
+/// An arbitrary-precision integer.
+///
+/// The magnitude is stored as base 2^32 digits ("limbs"), least significant
+/// first. It never has leading zero limbs, and zero is never negative, so that
+/// every value has exactly one representation and equality can be derived.
+#[derive(Clone, Default, Eq, PartialEq, Hash)]
+pub struct Integer {
+    negative: bool,
+    magnitude: Vec<u32>,
+}
+
+impl Integer {
+    fn from_parts(negative: bool, mut magnitude: Vec<u32>) -> Self {
+        while magnitude.last() == Some(&0) {
+            magnitude.pop();
+        }
+
+        Self {
+            negative: negative && !magnitude.is_empty(),
+            magnitude,
+        }
+    }
+
+    pub fn zero() -> Self {
+        Self::default()
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.magnitude.is_empty()
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.negative
+    }
+
+    /// Divides by `rhs`, returning the quotient and the remainder, which
+    /// always satisfy `self = rhs * quotient + remainder`. How a quotient that
+    /// isn't whole is rounded depends on `rounding`.
+    ///
+    /// Returns `None` when dividing by zero.
+    pub fn div_rem(&self, rhs: &Integer, rounding: Rounding) -> Option<(Integer, Integer)> {
+        if rhs.is_zero() {
+            return None;
+        }
+
+        let (quotient, remainder) = div_rem_magnitude(&self.magnitude, &rhs.magnitude);
+
+        // Truncated division gives the quotient the sign of the two operands,
+        // and the remainder the sign of the dividend.
+        let quotient = Integer::from_parts(self.negative != rhs.negative, quotient);
+        let remainder = Integer::from_parts(self.negative, remainder);
+
+        match rounding {
+            Rounding::Euclidean if remainder.is_negative() && rhs.is_negative() => {
+                Some((&quotient + &Integer::from(1), &remainder - rhs))
+            }
+            Rounding::Euclidean if remainder.is_negative() => {
+                Some((&quotient - &Integer::from(1), &remainder + rhs))
+            }
+            _ => Some((quotient, remainder)),
+        }
+    }
+
+    /// Converts to an `i64`, if the value fits in one.
+    pub fn to_i64(&self) -> Option<i64> {
+        if self.magnitude.len() > 2 {
+            return None;
+        }
+
+        let magnitude = self
+            .magnitude
+            .iter()
+            .rev()
+            .fold(0u64, |acc, &limb| (acc << 32) | limb as u64);
+
+        if self.negative {
+            0i64.checked_sub_unsigned(magnitude)
+        } else {
+            i64::try_from(magnitude).ok()
+        }
+    }
+}
+
+impl From<i64> for Integer {
+    fn from(value: i64) -> Self {
+        let magnitude = value.unsigned_abs();
+        Self::from_parts(value < 0, vec![magnitude as u32, (magnitude >> 32) as u32])
+    }
+}
+
+impl From<i32> for Integer {
+    fn from(value: i32) -> Self {
+        Self::from(value as i64)
+    }
+}
+
+impl Ord for Integer {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => cmp_magnitude(&self.magnitude, &other.magnitude),
+            (true, true) => cmp_magnitude(&other.magnitude, &self.magnitude),
+        }
+    }
+}
+
+impl PartialOrd for Integer {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Neg for Integer {
+    type Output = Integer;
+
+    fn neg(self) -> Self::Output {
+        Self::from_parts(!self.negative, self.magnitude)
+    }
+}
+
+impl Add for &Integer {
+    type Output = Integer;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        if self.negative == rhs.negative {
+            return Integer::from_parts(
+                self.negative,
+                add_magnitude(&self.magnitude, &rhs.magnitude),
+            );
+        }
+
+        // The signs differ, so the result takes the sign of whichever has the
+        // larger magnitude.
+        match cmp_magnitude(&self.magnitude, &rhs.magnitude) {
+            Ordering::Less => {
+                Integer::from_parts(rhs.negative, sub_magnitude(&rhs.magnitude, &self.magnitude))
+            }
+            _ => Integer::from_parts(
+                self.negative,
+                sub_magnitude(&self.magnitude, &rhs.magnitude),
+            ),
+        }
+    }
+}
+
+impl Sub for &Integer {
+    type Output = Integer;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        self + &-rhs.clone()
+    }
+}
+
+impl Mul for &Integer {
+    type Output = Integer;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        Integer::from_parts(
+            self.negative != rhs.negative,
+            mul_magnitude(&self.magnitude, &rhs.magnitude),
+        )
+    }
+}
+
+impl Display for Integer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.is_zero() {
+            return write!(f, "0");
+        }
+
+        // Peel off base 10^9 digits, least significant first.
+        let mut chunks = Vec::new();
+        let mut magnitude = self.magnitude.clone();
+
+        while !magnitude.is_empty() {
+            chunks.push(div_rem_small(&mut magnitude, 1_000_000_000));
+        }
+
+        if self.negative {
+            write!(f, "-")?;
+        }
+
+        let mut chunks = chunks.into_iter().rev();
+        write!(f, "{}", chunks.next().unwrap())?;
+
+        for chunk in chunks {
+            write!(f, "{chunk:09}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Writes the integer in decimal, the same as `Display`, rather than as limbs.
+impl Debug for Integer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{self}")
+    }
+}
+
+/// Parses an optionally negative string of decimal digits, like `-123`.
+impl FromStr for Integer {
+    type Err = ParseIntegerError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (negative, digits) = match s.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, s),
+        };
+
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(ParseIntegerError(s.to_string()));
+        }
+
+        let mut magnitude = Vec::new();
+
+        for digit in digits.bytes() {
+            mul_add_small(&mut magnitude, 10, (digit - b'0') as u32);
+        }
+
+        Ok(Self::from_parts(negative, magnitude))
+    }
+}
+
+/// Represents an error when parsing an [`Integer`] from a string.
+#[derive(Debug, Eq, PartialEq)]
+pub struct ParseIntegerError(pub String);
+
+impl Error for ParseIntegerError {}
+
+impl Display for ParseIntegerError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "`{}` is not an integer", self.0)
+    }
+}
+
+fn cmp_magnitude(a: &[u32], b: &[u32]) -> Ordering {
+    a.len()
+        .cmp(&b.len())
+        .then_with(|| a.iter().rev().cmp(b.iter().rev()))
+}
+
+fn add_magnitude(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let (longer, shorter) = if a.len() >= b.len() { (a, b) } else { (b, a) };
+
+    let mut result = Vec::with_capacity(longer.len() + 1);
+    let mut carry = 0u64;
+
+    for (i, &limb) in longer.iter().enumerate() {
+        let sum = limb as u64 + *shorter.get(i).unwrap_or(&0) as u64 + carry;
+        result.push(sum as u32);
+        carry = sum >> 32;
+    }
+
+    result.push(carry as u32);
+    result
+}
+
+/// Subtracts `b` from `a`, where `a` must have the larger magnitude.
+fn sub_magnitude(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut result = Vec::with_capacity(a.len());
+    let mut borrow = 0i64;
+
+    for (i, &limb) in a.iter().enumerate() {
+        let mut difference = limb as i64 - *b.get(i).unwrap_or(&0) as i64 - borrow;
+
+        borrow = if difference < 0 {
+            difference += 1 << 32;
+            1
+        } else {
+            0
+        };
+
+        result.push(difference as u32);
+    }
+
+    result
+}
+
+fn mul_magnitude(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut result = vec![0u32; a.len() + b.len()];
+
+    for (i, &x) in a.iter().enumerate() {
+        let mut carry = 0u64;
+
+        for (j, &y) in b.iter().enumerate() {
+            let product = x as u64 * y as u64 + result[i + j] as u64 + carry;
+            result[i + j] = product as u32;
+            carry = product >> 32;
+        }
+
+        result[i + b.len()] = carry as u32;
+    }
+
+    result
+}
+
+/// Multiplies a magnitude by a small factor and adds a small addend, in place.
+fn mul_add_small(magnitude: &mut Vec<u32>, factor: u32, addend: u32) {
+    let mut carry = addend as u64;
+
+    for limb in magnitude.iter_mut() {
+        let product = *limb as u64 * factor as u64 + carry;
+        *limb = product as u32;
+        carry = product >> 32;
+    }
+
+    if carry > 0 {
+        magnitude.push(carry as u32);
+    }
+}
+
+/// Divides one magnitude by another, which mustn't be zero, returning the
+/// quotient and the remainder.
+fn div_rem_magnitude(a: &[u32], b: &[u32]) -> (Vec<u32>, Vec<u32>) {
+    if let [divisor] = b {
+        let mut quotient = a.to_vec();
+        let remainder = div_rem_small(&mut quotient, *divisor);
+        return (quotient, vec![remainder]);
+    }
+
+    // Long division, one bit at a time. Slow, but large divisors are rare.
+    let mut quotient = vec![0u32; a.len()];
+    let mut remainder = Vec::new();
+
+    for i in (0..a.len() * 32).rev() {
+        let bit = (a[i / 32] >> (i % 32)) & 1;
+        mul_add_small(&mut remainder, 2, bit);
+
+        if cmp_magnitude(&remainder, b) != Ordering::Less {
+            remainder = sub_magnitude(&remainder, b);
+
+            while remainder.last() == Some(&0) {
+                remainder.pop();
+            }
+
+            quotient[i / 32] |= 1 << (i % 32);
+        }
+    }
+
+    (quotient, remainder)
+}
+
+/// Divides a magnitude by a small divisor in place, returning the remainder.
+fn div_rem_small(magnitude: &mut Vec<u32>, divisor: u32) -> u32 {
+    let mut remainder = 0u64;
+
+    for limb in magnitude.iter_mut().rev() {
+        let current = (remainder << 32) | *limb as u64;
+        *limb = (current / divisor as u64) as u32;
+        remainder = current % divisor as u64;
+    }
+
+    while magnitude.last() == Some(&0) {
+        magnitude.pop();
+    }
+
+    remainder as u32
+}
+
+/// The range of integers that a program may use. Literals outside of this
+/// range are rejected by the parser, and arithmetic that leaves it is an error
+/// in the interpreter.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum IntegerType {
+    /// 32-bit signed integers.
+    #[default]
+    I32,
+    /// 64-bit signed integers.
+    I64,
+    /// Arbitrary-precision integers, which never overflow.
+    Big,
+}
+
+impl IntegerType {
+    /// Whether the value is in this type's range.
+    pub fn contains(&self, value: &Integer) -> bool {
+        match self {
+            IntegerType::I32 => value.to_i64().is_some_and(|x| i32::try_from(x).is_ok()),
+            IntegerType::I64 => value.to_i64().is_some(),
+            IntegerType::Big => true,
+        }
+    }
+}
+
+impl Display for IntegerType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            IntegerType::I32 => "i32",
+            IntegerType::I64 => "i64",
+            IntegerType::Big => "big",
+        };
+
+        write!(f, "{name}")
+    }
+}
+
+/// Parses the names used by the `Display` impl: `i32`, `i64` and `big`.
+impl FromStr for IntegerType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "i32" => Ok(IntegerType::I32),
+            "i64" => Ok(IntegerType::I64),
+            "big" => Ok(IntegerType::Big),
+            _ => Err(format!(
+                "Unknown integer type `{s}`; expected `i32`, `i64` or `big`"
+            )),
+        }
+    }
+}
+
+/// How integer division rounds a quotient that isn't whole. Either way, the
+/// remainder of `a % b` makes up the difference, so `a = b * (a / b) + a % b`.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum Rounding {
+    /// Rounds towards zero, like Rust and C do, so `-7 / 2 = -3` and
+    /// `-7 % 2 = -1`. The remainder has the sign of the dividend.
+    #[default]
+    Truncated,
+    /// Rounds so that the remainder is never negative, so `-7 / 2 = -4` and
+    /// `-7 % 2 = 1`.
+    Euclidean,
+}
+
+impl Display for Rounding {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Rounding::Truncated => "truncated",
+            Rounding::Euclidean => "euclidean",
+        };
+
+        write!(f, "{name}")
+    }
+}
+
+/// Parses the names used by the `Display` impl: `truncated` and `euclidean`.
+impl FromStr for Rounding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "truncated" => Ok(Rounding::Truncated),
+            "euclidean" => Ok(Rounding::Euclidean),
+            _ => Err(format!(
+                "Unknown rounding `{s}`; expected `truncated` or `euclidean`"
+            )),
+        }
+    }
+}
@@ -1,28 +1,60 @@
+mod block_style;
+mod category;
 mod parse_error;
 
-use crate::ast::Ast;
+pub use crate::parser::block_style::BlockStyle;
+pub use crate::parser::category::Category;
+pub use crate::parser::parse_error::{ParseError, ParseErrorKind};
+
+use crate::ast::{Ast, Sugar};
+use crate::cst::{Checkpoint, GreenNodeBuilder, NodeKind, SyntaxNode};
 use crate::lexer::{Span, Spanned, Token};
-use crate::parser::parse_error::ParseError;
+use crate::number::{Integer, IntegerType};
+use crate::parser::parse_error::{EXPRESSION_START, STATEMENT_START};
 use crate::{
-    add, and, ass_stmt, binary_node, comp_stmt, eq, if_stmt, less_eq, literal, mul, not, skip_stmt,
-    sub, while_stmt,
+    add, and, ass_stmt, binary_node, comp_stmt, div, eq, if_stmt, less_eq, literal, modulo, mul,
+    not, skip_stmt, sub,
 };
 
-use std::iter::Peekable;
+use std::collections::VecDeque;
 
 /// A parser that transforms a stream of tokens into an AST.
 /// Needs a reference to the source, to be able to extract identifiers and
 /// literal values.
 ///
 /// The parser uses recursive descent, and distinguishes between statements
-/// and expressions.
+/// and expressions. As it goes, it also builds a lossless concrete syntax tree,
+/// which [`parse_with_cst`](Parser::parse_with_cst) returns.
 #[derive(Debug)]
 pub struct Parser<'a, I>
 where
     I: Iterator<Item = Spanned<Token>>,
 {
     source: &'a str,
-    tokens: Peekable<I>,
+    tokens: I,
+    /// Tokens that have been taken from `tokens` to look at, but haven't been
+    /// consumed yet, trivia included.
+    lookahead: VecDeque<Spanned<Token>>,
+    integer_type: IntegerType,
+    block_style: BlockStyle,
+    builder: GreenNodeBuilder,
+    /// Trivia that have been skipped over, but not yet added to the concrete
+    /// syntax tree.
+    trivia: Vec<Spanned<Token>>,
+    /// The end of the last token that was consumed, for working out the spans
+    /// of nodes.
+    previous_end: usize,
+    /// The last token that was consumed, for working out the likely cause of
+    /// errors.
+    previous_token: Option<Token>,
+    /// The `(`, `[[`, `{` and `begin` that have been consumed without being
+    /// closed.
+    open_brackets: Vec<Token>,
+    /// Whether statements that fail to parse are recovered from, rather than
+    /// ending the parse.
+    recovering: bool,
+    /// The errors that have been recovered from.
+    errors: Vec<ParseError>,
 }
 
 impl<'a, I> Parser<'a, I>
@@ -35,62 +67,246 @@ where
     ) -> Self {
         Self {
             source,
-            tokens: tokens.into_iter().peekable(),
+            tokens: tokens.into_iter(),
+            lookahead: VecDeque::new(),
+            integer_type: IntegerType::default(),
+            block_style: BlockStyle::default(),
+            builder: GreenNodeBuilder::new(),
+            trivia: Vec::new(),
+            previous_end: 0,
+            previous_token: None,
+            open_brackets: Vec::new(),
+            recovering: false,
+            errors: Vec::new(),
         }
     }
 
+    /// Sets the range that integer literals must be within. Literals outside of
+    /// this range are reported as parse errors.
+    pub fn set_integer_type(&mut self, integer_type: IntegerType) {
+        self.integer_type = integer_type;
+    }
+
+    /// Sets how the bodies of `if` and `while` statements are delimited.
+    pub fn set_block_style(&mut self, block_style: BlockStyle) {
+        self.block_style = block_style;
+    }
+
     /// Returns the next Spanned<Token>, and advances the stream in doing so.
     /// When the token stream is finished, returns `None`.
-    /// Skips `Whitespace` and `LineBreak` tokens.
+    /// Skips trivia tokens, like `Whitespace` and comments.
     fn advance(&mut self) -> Option<Spanned<Token>> {
-        let mut next_token = self.tokens.next();
+        self.peek();
+        let next_token = self.lookahead.pop_front()?;
 
-        while next_token
-            .is_some_and(|spanned| matches!(spanned.inner, Token::Whitespace | Token::LineBreak))
-        {
-            next_token = self.tokens.next();
+        self.previous_end = next_token.span.1;
+        self.previous_token = Some(next_token.inner);
+
+        match next_token.inner {
+            Token::LeftParen | Token::LeftSemantic | Token::LeftBrace | Token::Begin => {
+                self.open_brackets.push(next_token.inner)
+            }
+            closer
+            @ (Token::RightParen | Token::RightSemantic | Token::RightBrace | Token::End)
+                if self.open_brackets.last() == Some(&opener(closer)) =>
+            {
+                self.open_brackets.pop();
+            }
+            _ => {}
         }
 
-        next_token
+        self.push_token(next_token);
+        Some(next_token)
     }
 
     /// Returns a reference to the next Spanned<Token>, and does _not_ advance
     /// the stream in doing so.
     ///
     /// When the token stream is finished, returns `None`.
-    /// Skips `Whitespace` and `LineBreak` tokens.
+    /// Skips trivia tokens, like `Whitespace` and comments.
     fn peek(&mut self) -> Option<&Spanned<Token>> {
+        self.fill_lookahead(1);
+
         while self
-            .tokens
-            .peek()
-            .is_some_and(|spanned| matches!(spanned.inner, Token::Whitespace | Token::LineBreak))
+            .lookahead
+            .front()
+            .is_some_and(|spanned| spanned.inner.is_trivia())
         {
-            self.tokens.next();
+            let trivia = self.lookahead.pop_front().unwrap();
+            self.trivia.push(trivia);
+            self.fill_lookahead(1);
+        }
+
+        self.lookahead.front()
+    }
+
+    /// Returns the kind of the `n`th token after the next one, skipping
+    /// trivia, without consuming anything. `peek_nth(0)` is the next token.
+    fn peek_nth(&mut self, n: usize) -> Option<Token> {
+        self.peek()?;
+
+        let mut remaining = n;
+        let mut index = 0;
+
+        loop {
+            self.fill_lookahead(index + 1);
+            let token = self.lookahead.get(index)?.inner;
+
+            if !token.is_trivia() {
+                if remaining == 0 {
+                    return Some(token);
+                }
+                remaining -= 1;
+            }
+
+            index += 1;
         }
+    }
 
-        self.tokens.peek()
+    /// Takes tokens from the stream until at least `len` are being looked at,
+    /// or the stream is finished.
+    fn fill_lookahead(&mut self, len: usize) {
+        while self.lookahead.len() < len {
+            let Some(token) = self.tokens.next() else {
+                break;
+            };
+            self.lookahead.push_back(token);
+        }
+    }
+
+    /// Adds a token that has been consumed to the concrete syntax tree, after
+    /// any trivia before it. Every token goes through here exactly once,
+    /// trivia included, which is what keeps the tree lossless.
+    fn push_token(&mut self, token: Spanned<Token>) {
+        self.flush_trivia();
+
+        let Span(start, end) = token.span;
+        self.builder.token(token.inner, &self.source[start..end]);
+    }
+
+    /// Adds any skipped trivia to the concrete syntax tree.
+    fn flush_trivia(&mut self) {
+        for Spanned { inner, span } in self.trivia.drain(..) {
+            self.builder.token(inner, &self.source[span.0..span.1]);
+        }
+    }
+
+    /// Opens a node in the concrete syntax tree. Trivia are never at the edges
+    /// of a node: the ones before its first token are kept in the parent, and
+    /// so are the ones after its last, since they aren't added until later.
+    fn start_node(&mut self, kind: NodeKind) {
+        self.peek();
+        self.flush_trivia();
+        self.builder.start_node(kind);
+    }
+
+    /// Marks where a node may start, for when its kind isn't known until after
+    /// its first child has been parsed.
+    fn checkpoint(&mut self) -> Checkpoint {
+        self.peek();
+        self.flush_trivia();
+        self.builder.checkpoint()
+    }
+
+    fn finish_node(&mut self) {
+        self.builder.finish_node();
+    }
+
+    /// The start of the next token, which is where the next node starts.
+    fn next_start(&mut self) -> usize {
+        match self.peek() {
+            Some(Spanned { span, .. }) => span.0,
+            None => self.source.len(),
+        }
+    }
+
+    /// The span from `start` up to the end of the last token consumed.
+    fn span_from(&self, start: usize) -> Span {
+        Span(start, self.previous_end)
+    }
+
+    /// The span used for errors that occur at the end of the token stream. This
+    /// is the last character of the source, or an empty span if the source is
+    /// empty.
+    fn eof_span(&self) -> Span {
+        let last_char_start = self
+            .source
+            .char_indices()
+            .next_back()
+            .map_or(0, |(index, _)| index);
+
+        Span(last_char_start, self.source.len())
     }
 
     /// Checks that the next token is as specified. If it is, the token is
-    /// consumed and the stream advances. If not, return a ParseError.
+    /// consumed and the stream advances. If not, return a ParseError, leaving
+    /// the token to be recovered from.
     fn expect_token(&mut self, token: Token) -> Result<(), ParseError> {
-        let retrieved = self.advance();
+        match self.peek() {
+            Some(Spanned { inner, .. }) if *inner == token => {
+                self.advance();
+                Ok(())
+            }
 
-        match retrieved {
-            Some(Spanned { inner, .. }) if inner == token => Ok(()),
+            _ => Err(self.unexpected(&[token])),
+        }
+    }
 
-            Some(Spanned { inner, span }) => Err(ParseError {
-                message: format!("Expected {token:?}, found {inner:?}",),
-                span,
-            }),
+    /// Makes an error for the next token, which should have been one of
+    /// `expected`. The token is left in the stream.
+    fn unexpected(&mut self, expected: &[Token]) -> ParseError {
+        let error = match self.peek() {
+            Some(&Spanned { inner, span }) => ParseError::unexpected(expected, Some(inner), span),
+            None => ParseError::unexpected(expected, None, self.eof_span()),
+        };
 
-            _ => Err(ParseError {
-                message: format!("Expected {token:?}, but reached end of token stream",),
-                span: Span(self.source.len() - 1, self.source.len()),
-            }),
+        match self.likely_cause(&error) {
+            Some(note) => error.with_note(note),
+            None => error,
         }
     }
 
+    /// Works out the likely cause of an error, for the mistakes that are
+    /// common enough to be worth pointing out.
+    fn likely_cause(&self, error: &ParseError) -> Option<&'static str> {
+        let stray = error.expected.is_empty() || error.expected == STATEMENT_START;
+
+        let note = match (self.previous_token, error.found?) {
+            // if b then x := 1;; else x := 2
+            (Some(Token::Semicolon), Token::Semicolon) if stray => {
+                "two `;`s in a row leave an empty statement between them, so remove one of them"
+            }
+            // while b do x := 1; else x := 2
+            (Some(Token::Semicolon), Token::Else) if stray => {
+                "the `;` before this `else` ends the statement, so there's no `if` for the `else` to belong to"
+            }
+            (_, Token::Else) if stray => "there's no `if` for this `else` to belong to",
+            // x = 1
+            (_, Token::Equal) if error.expected == [Token::Assign] => {
+                "use `:=` to assign to a variable, as `=` compares two values"
+            }
+            // if x := 1 then ...
+            (_, Token::Assign) if matches!(error.expected.last(), Some(Token::Then | Token::Do)) => {
+                "use `=` to compare two values, as `:=` assigns to a variable"
+            }
+            // while b do begin x := 1 end
+            (Some(Token::Begin), _) if error.expected == [Token::Var] => {
+                "`begin ... end` blocks without `var` declarations are only allowed in the delimited block style"
+            }
+            // while b do { x := 1 }
+            (_, Token::LeftBrace) if stray && self.block_style == BlockStyle::Greedy => {
+                "`{ }` blocks are only allowed in the delimited block style, so use `( )` to group statements"
+            }
+            // while b do x := 1
+            (_, _) if error.expected == [Token::LeftBrace, Token::Begin] => {
+                "in the delimited block style, the bodies of `if` and `while` go in `{ }` or `begin ... end`"
+            }
+            _ => return None,
+        };
+
+        Some(note)
+    }
+
     /// Optionally expects a token. If the token exists, acts like `expect_token`.
     /// Otherwise, does nothing, not even returning an error or panic!()-ing.
     fn maybe_expect_token(&mut self, token: Token) {
@@ -103,14 +319,186 @@ where
     }
 
     // Parse the token stream into an abstract syntax tree representing the
-    // structure of the program. Currently, this is span-less.
-    pub fn parse(&mut self) -> Result<Ast, ParseError> {
+    // structure of the program. Every node has the span of the source it was
+    // parsed from.
+    pub fn parse(&mut self) -> Result<Spanned<Ast>, ParseError> {
         self.stmt_block()
     }
 
-    fn stmt_block(&mut self) -> Result<Ast, ParseError> {
+    /// Parses the whole token stream without stopping at the first error.
+    ///
+    /// Returns a tree with an `Ast::Error` standing in for each statement that
+    /// couldn't be parsed, along with every error. After an error, tokens are
+    /// skipped up to the next `;`, `else`, or unmatched `)`, `]]`, `}` or
+    /// `end`, and parsing carries on from there.
+    pub fn parse_recovering(&mut self) -> (Spanned<Ast>, Vec<ParseError>) {
+        self.recovering = true;
+
+        let mut ast = self.recovering_stmt_block();
+
+        // The statement block ends early at a `)`, `else`, `]]`, `}` or `end`
+        // that doesn't belong to anything, so carry on past it.
+        while let Some(&Spanned { span, .. }) = self.peek() {
+            let error = self.unexpected(&[]);
+            self.report(error);
+            self.advance();
+
+            let error = Spanned::new(Ast::Error, span);
+            let span = Span(ast.span.0, span.1);
+            ast = Spanned::new(comp_stmt!(ast, error), span);
+
+            if self.peek().is_some() {
+                let block = self.recovering_stmt_block();
+                let span = Span(ast.span.0, block.span.1);
+                ast = Spanned::new(comp_stmt!(ast, block), span);
+            }
+        }
+
+        self.recovering = false;
+        (ast, std::mem::take(&mut self.errors))
+    }
+
+    /// Parses a statement block, turning an error into an `Ast::Error` if one
+    /// gets through.
+    fn recovering_stmt_block(&mut self) -> Spanned<Ast> {
+        let start = self.next_start();
+
+        self.stmt_block().unwrap_or_else(|e| {
+            self.report(e);
+            Spanned::new(Ast::Error, Span(start, self.previous_end.max(start)))
+        })
+    }
+
+    /// Records an error that has been recovered from. Only the first error at
+    /// any one place is kept, since the rest tend to be knock-on effects.
+    fn report(&mut self, error: ParseError) {
+        if !self.errors.iter().any(|e| e.span.0 == error.span.0) {
+            self.errors.push(error);
+        }
+    }
+
+    /// Parses a statement. When recovering, an error is recorded, and the
+    /// statement is replaced with an `Ast::Error` spanning the code that was
+    /// skipped over.
+    fn recovering_statement(&mut self) -> Result<Spanned<Ast>, ParseError> {
+        let start = self.next_start();
+        let open_brackets = self.open_brackets.len();
+        let depth = self.builder.depth();
+
+        let error = match self.statement() {
+            Ok(stmt) => return Ok(stmt),
+            Err(e) if self.recovering => e,
+            Err(e) => return Err(e),
+        };
+
+        self.report(error);
+
+        while self.builder.depth() > depth {
+            self.finish_node();
+        }
+
+        self.synchronise(open_brackets);
+        Ok(Spanned::new(
+            Ast::Error,
+            Span(start, self.previous_end.max(start)),
+        ))
+    }
+
+    /// Skips tokens up to one that a statement can end at: a `;` or `else`, or
+    /// a `)`, `]]`, `}` or `end` outside of any brackets opened by the failed
+    /// statement.
+    /// `open_brackets` is how many brackets were open before it started.
+    fn synchronise(&mut self, open_brackets: usize) {
+        while let Some(&Spanned { inner, .. }) = self.peek() {
+            let at_end = match inner {
+                Token::Semicolon | Token::Else => true,
+                Token::RightParen | Token::RightSemantic | Token::RightBrace | Token::End => {
+                    self.open_brackets.len() <= open_brackets
+                }
+                _ => false,
+            };
+
+            if at_end {
+                break;
+            }
+
+            self.advance();
+        }
+
+        // Anything opened within the failed statement is abandoned.
+        self.open_brackets.truncate(open_brackets);
+    }
+
+    /// Parses the token stream like [`parse`](Parser::parse), and also returns
+    /// a lossless concrete syntax tree of the whole source.
+    ///
+    /// The tree is returned even if parsing fails. Any nodes left open by the
+    /// error are closed, and the tokens that weren't parsed are kept in the
+    /// root node, so the tree always has exactly the source's text.
+    pub fn parse_with_cst(&mut self) -> (Result<Spanned<Ast>, ParseError>, SyntaxNode) {
+        self.builder = GreenNodeBuilder::new();
+        self.trivia.clear();
+        self.builder.start_node(NodeKind::Root);
+
+        let ast = self.parse();
+
+        while self.builder.depth() > 1 {
+            self.finish_node();
+        }
+
+        while self.advance().is_some() {}
+        self.flush_trivia();
+
+        let green = std::mem::take(&mut self.builder).finish();
+        (ast, SyntaxNode::new_root(green))
+    }
+
+    /// Parses the token stream as a single expression, rather than as a
+    /// statement block. The whole token stream must be consumed.
+    pub(crate) fn parse_expression(&mut self) -> Result<Spanned<Ast>, ParseError> {
+        let expr = self.expression()?;
+        self.expect_end()?;
+        Ok(expr)
+    }
+
+    /// Parses the whole token stream as a statement block, checking that every
+    /// part of it is of the right category, so `x := true` is an error.
+    pub fn parse_stmt(&mut self) -> Result<Spanned<Ast>, ParseError> {
+        let stmt = self.stmt_block()?;
+        self.expect_end()?;
+        category::check(&stmt, Category::Stmt)?;
+        Ok(stmt)
+    }
+
+    /// Parses the whole token stream as an arithmetic expression, like
+    /// `x * (y + 1)`.
+    pub fn parse_aexp(&mut self) -> Result<Spanned<Ast>, ParseError> {
+        let expr = self.parse_expression()?;
+        category::check(&expr, Category::AExp)?;
+        Ok(expr)
+    }
+
+    /// Parses the whole token stream as a boolean expression, like
+    /// `x <= 1 & !(y = 2)`.
+    pub fn parse_bexp(&mut self) -> Result<Spanned<Ast>, ParseError> {
+        let expr = self.parse_expression()?;
+        category::check(&expr, Category::BExp)?;
+        Ok(expr)
+    }
+
+    /// Checks that the whole token stream has been consumed.
+    fn expect_end(&mut self) -> Result<(), ParseError> {
+        match self.peek() {
+            None => Ok(()),
+            Some(_) => Err(self.unexpected(&[])),
+        }
+    }
+
+    fn stmt_block(&mut self) -> Result<Spanned<Ast>, ParseError> {
         // <stmt_block> ::= <statement> (";" <statement>)* ";"?
 
+        self.start_node(NodeKind::StmtBlock);
+
         // This is temporary, to allow multiline source files.
         // Internally, newlines are condensed to semicolons more liberally than
         // suitable, so the ASI logic needs to be expanded upon.
@@ -121,7 +509,7 @@ where
             self.advance();
         }
 
-        let mut stmt = self.statement()?;
+        let mut stmt = self.recovering_statement()?;
 
         // If the next token is a semicolon, remove it and find the next <statement>.
         while let Some(Spanned {
@@ -131,6 +519,13 @@ where
         {
             self.advance();
 
+            // Line breaks on either side of a comment or a blank line lex as
+            // separate `;`s. Any `;`s with only trivia between them are one
+            // separator, so only `;;` leaves an empty statement.
+            while self.next_is(Token::Semicolon) && self.next_start() > self.previous_end {
+                self.advance();
+            }
+
             // These tokens indicate an end of the statement block, and so we
             // now pass control back to the function that called them.
             // For example, maybe we reached here after advancing the semicolon:
@@ -142,38 +537,163 @@ where
             match self.peek() {
                 None
                 | Some(Spanned {
-                    inner: Token::RightParen | Token::Else | Token::RightSemantic,
+                    inner:
+                        Token::RightParen
+                        | Token::Else
+                        | Token::RightSemantic
+                        | Token::RightBrace
+                        | Token::End,
                     ..
                 }) => break,
                 _ => {}
             };
 
-            let second_statement = self.statement()?;
+            let second_statement = self.recovering_statement()?;
+            let span = Span(stmt.span.0, second_statement.span.1);
 
-            stmt = comp_stmt!(stmt, second_statement);
+            stmt = Spanned::new(comp_stmt!(stmt, second_statement), span);
         }
 
         // The statement block may end with a semicolon, but it's completely
         // optional.
         self.maybe_expect_token(Token::Semicolon);
+        self.finish_node();
         Ok(stmt)
     }
 
-    fn statement(&mut self) -> Result<Ast, ParseError> {
-        // <statement> ::= <if_stmt>
+    fn statement(&mut self) -> Result<Spanned<Ast>, ParseError> {
+        // <statement> ::= <assertion> <statement>?
+        //               | <command> <assertion>?
+        //
+        // `{P} S {Q}`, where `S` is a command, is a Hoare triple. Otherwise,
+        // an assertion is composed with the statement before or after it.
+
+        let start = self.next_start();
+        let checkpoint = self.checkpoint();
+
+        if !self.next_is_assertion() {
+            let command = self.command()?;
+
+            if !self.next_is_assertion() {
+                return Ok(command);
+            }
+
+            let post = self.assertion()?;
+            return Ok(Spanned::new(
+                comp_stmt!(command, post),
+                self.span_from(start),
+            ));
+        }
+
+        let pre = self.assertion()?;
+
+        let next = self.peek().map(|spanned| spanned.inner);
+
+        let result = match next {
+            Some(Token::LeftBrace) if self.next_is_assertion() => {
+                comp_stmt!(pre, self.statement()?)
+            }
+
+            Some(token) if STATEMENT_START.contains(&token) => {
+                let command = self.command()?;
+
+                if !self.next_is_assertion() {
+                    return Ok(Spanned::new(
+                        comp_stmt!(pre, command),
+                        self.span_from(start),
+                    ));
+                }
+
+                let post = self.assertion()?;
+                self.builder.start_node_at(checkpoint, NodeKind::Triple);
+                self.finish_node();
+
+                Ast::Triple {
+                    pre: formula(pre),
+                    stmt: Box::new(command),
+                    post: formula(post),
+                }
+            }
+
+            _ => return Ok(pre),
+        };
+
+        Ok(Spanned::new(result, self.span_from(start)))
+    }
+
+    /// Whether the next token is `token`.
+    fn next_is(&mut self, token: Token) -> bool {
+        self.peek().is_some_and(|spanned| spanned.inner == token)
+    }
+
+    /// Whether an assertion is next: a `{` that doesn't open a block.
+    fn next_is_assertion(&mut self) -> bool {
+        self.next_is(Token::LeftBrace) && !self.brace_opens_block()
+    }
+
+    /// Whether the `{` that's next opens a block of statements rather than an
+    /// assertion, going by what comes after it. A block starts with something
+    /// that can't start a formula: a line break, a keyword that starts a
+    /// statement, another `{`, or a name that's assigned to or run. Anything
+    /// else, including `(`, is taken to start a formula.
+    fn brace_opens_block(&mut self) -> bool {
+        match self.peek_nth(1) {
+            Some(Token::Identifier) => matches!(
+                self.peek_nth(2),
+                Some(
+                    Token::Assign
+                        | Token::LeftParen
+                        | Token::Semicolon
+                        | Token::LeftBrace
+                        | Token::RightBrace
+                )
+            ),
+            Some(
+                Token::Semicolon
+                | Token::If
+                | Token::While
+                | Token::Skip
+                | Token::Assert
+                | Token::Assume
+                | Token::LeftBrace
+                | Token::Begin,
+            ) => true,
+            _ => false,
+        }
+    }
+
+    fn assertion(&mut self) -> Result<Spanned<Ast>, ParseError> {
+        // <assertion> ::= "{" <expression> "}"
+
+        let start = self.next_start();
+
+        self.start_node(NodeKind::Assertion);
+        self.expect_token(Token::LeftBrace)?;
+        let formula = self.expression()?;
+        self.expect_token(Token::RightBrace)?;
+        self.finish_node();
+
+        let assertion = Ast::Assertion {
+            formula: Box::new(formula),
+        };
+        Ok(Spanned::new(assertion, self.span_from(start)))
+    }
+
+    fn command(&mut self) -> Result<Spanned<Ast>, ParseError> {
+        // <command> ::= <if_stmt>
         //               | <while_stmt>
         //               | <ass_stmt>
         //               | <skip_stmt>
+        //               | <check_stmt>
         //               | "(" <statement> ")"
+        //               | <delimited_block>    ("{" only in the delimited block style)
 
-        let (keyword, span) = match self.peek() {
-            Some(Spanned { inner, span }) => (inner, span),
-            None => {
-                return Err(ParseError {
-                    message: "Unexpected end of token stream".to_string(),
-                    span: Span(self.source.len() - 1, self.source.len()),
-                })
-            }
+        let Some(&Spanned {
+            inner: keyword,
+            span,
+        }) = self.peek()
+        else {
+            return Err(self.unexpected(STATEMENT_START));
         };
 
         match keyword {
@@ -181,85 +701,254 @@ where
             Token::While => self.while_stmt(),
             Token::Identifier => self.ass_stmt(),
             Token::Skip => self.skip_stmt(),
+            Token::Assert | Token::Assume => self.check_stmt(),
             Token::LeftParen => {
+                // The block's span includes the parentheses, so that they can
+                // be told apart from the statements within.
+                let start = span.0;
+
+                self.start_node(NodeKind::ParenStmt);
                 self.advance();
                 let block = self.stmt_block();
                 self.expect_token(Token::RightParen)?;
-                block
+                self.finish_node();
+
+                block.map(|block| Spanned::new(block.inner, self.span_from(start)))
             }
-            _ => Err(ParseError {
-                message: format!("Found {keyword:?}"),
-                span: *span,
-            }),
+            Token::Begin => self.delimited_block(),
+            Token::LeftBrace if self.block_style == BlockStyle::Delimited => self.delimited_block(),
+            _ => Err(self.unexpected(STATEMENT_START)),
+        }
+    }
+
+    fn body(&mut self) -> Result<Spanned<Ast>, ParseError> {
+        // <body> ::= <stmt_block>          (in the greedy block style)
+        //          | <delimited_block>     (in the delimited block style)
+
+        match self.block_style {
+            BlockStyle::Greedy => self.stmt_block(),
+            BlockStyle::Delimited => self.delimited_block(),
         }
     }
 
-    fn if_stmt(&mut self) -> Result<Ast, ParseError> {
-        // <if_stmt> ::= "if" <expression> "then" <stmt_block> ( "else" <stmt_block> )?
+    fn delimited_block(&mut self) -> Result<Spanned<Ast>, ParseError> {
+        // <delimited_block> ::= "{" <stmt_block> "}"
+        //                     | "begin" <declaration>* <stmt_block> "end"
+        //
+        // Only blocks with declarations are allowed in the greedy block style.
 
+        let (start, closer) = match self.peek() {
+            Some(&Spanned {
+                inner: Token::LeftBrace,
+                span,
+            }) => (span.0, Token::RightBrace),
+            Some(&Spanned {
+                inner: Token::Begin,
+                span,
+            }) => (span.0, Token::End),
+            _ => return Err(self.unexpected(&[Token::LeftBrace, Token::Begin])),
+        };
+
+        // Like a statement block in parentheses, the span includes the
+        // delimiters.
+        let checkpoint = self.checkpoint();
+        self.advance();
+
+        // A line break after the opening delimiter is a `;`.
+        self.maybe_expect_token(Token::Semicolon);
+
+        if closer == Token::End && self.next_is(Token::Var) {
+            return self.local_block(checkpoint, start);
+        }
+
+        // Blocks without declarations would mean the same as brackets in the
+        // greedy style.
+        if self.block_style == BlockStyle::Greedy {
+            return Err(self.unexpected(&[Token::Var]));
+        }
+
+        self.builder
+            .start_node_at(checkpoint, NodeKind::DelimitedBlock);
+        let block = self.stmt_block()?;
+        self.expect_token(closer)?;
+        self.finish_node();
+
+        Ok(Spanned::new(block.inner, self.span_from(start)))
+    }
+
+    /// Parses the rest of a `begin ... end` block with local variables, after
+    /// the `begin`.
+    fn local_block(
+        &mut self,
+        checkpoint: Checkpoint,
+        start: usize,
+    ) -> Result<Spanned<Ast>, ParseError> {
+        self.builder.start_node_at(checkpoint, NodeKind::LocalBlock);
+
+        let mut declarations = Vec::new();
+
+        while self.next_is(Token::Var) {
+            declarations.push(self.declaration()?);
+        }
+
+        let body = self.stmt_block()?;
+        self.expect_token(Token::End)?;
+        self.finish_node();
+
+        let block = Ast::Block {
+            declarations,
+            body: Box::new(body),
+        };
+        Ok(Spanned::new(block, self.span_from(start)))
+    }
+
+    fn declaration(&mut self) -> Result<(String, Spanned<Ast>), ParseError> {
+        // <declaration> ::= "var" <ident> ":=" <expression> ";"
+
+        self.start_node(NodeKind::Declaration);
+        self.expect_token(Token::Var)?;
+
+        let ident = match self.peek() {
+            Some(&Spanned {
+                inner: Token::Identifier,
+                span,
+            }) => {
+                self.advance();
+                self.source[span.0..span.1].to_string()
+            }
+            _ => return Err(self.unexpected(&[Token::Identifier])),
+        };
+
+        self.expect_token(Token::Assign)?;
+        let value = self.expression()?;
+        self.expect_token(Token::Semicolon)?;
+        self.finish_node();
+
+        Ok((ident, value))
+    }
+
+    fn if_stmt(&mut self) -> Result<Spanned<Ast>, ParseError> {
+        // <if_stmt> ::= "if" <expression> "then" <body> ( "else" <body> )?
+
+        let start = self.next_start();
+
+        self.start_node(NodeKind::IfStmt);
         self.expect_token(Token::If)?;
         let cond = self.expression()?;
 
         self.expect_token(Token::Then)?;
-        let block_true = self.stmt_block()?;
+        let block_true = self.body()?;
 
         let result = if let Some(Spanned {
             inner: Token::Else, ..
         }) = self.peek()
         {
             self.advance();
-            let block_false = self.stmt_block()?;
+            let block_false = self.body()?;
             if_stmt!(cond, block_true, block_false)
         } else {
-            if_stmt!(cond, block_true, skip_stmt!())
+            // The implicit `else skip` is empty, and comes at the end.
+            let skip = Spanned::new(skip_stmt!(), Span(self.previous_end, self.previous_end));
+            if_stmt!(cond, block_true, skip)
         };
 
-        Ok(result)
+        self.finish_node();
+        Ok(Spanned::new(result, self.span_from(start)))
     }
 
-    fn while_stmt(&mut self) -> Result<Ast, ParseError> {
-        // <while_stmt> ::= "while" <expression> "do" <stmt_block>
+    fn while_stmt(&mut self) -> Result<Spanned<Ast>, ParseError> {
+        // <while_stmt> ::= "while" <expression> ( "inv" <expression> )?
+        //                  ( "var" <expression> )? "do" <body>
+
+        let start = self.next_start();
 
+        self.start_node(NodeKind::WhileStmt);
         self.expect_token(Token::While)?;
         let cond = self.expression()?;
 
-        self.expect_token(Token::Do)?;
-        let body = self.stmt_block()?;
+        let invariant = self.annotation(Token::Inv)?;
+        let variant = self.annotation(Token::Var)?;
+
+        if !self.next_is(Token::Do) {
+            let expected: &[Token] = match (&invariant, &variant) {
+                (None, None) => &[Token::Inv, Token::Var, Token::Do],
+                (Some(_), None) => &[Token::Var, Token::Do],
+                (_, Some(_)) => &[Token::Do],
+            };
 
-        Ok(while_stmt!(cond, body))
+            return Err(self.unexpected(expected));
+        }
+
+        self.advance();
+        let body = self.body()?;
+
+        self.finish_node();
+
+        let result = Ast::While {
+            cond: Box::new(cond),
+            body: Box::new(body),
+            invariant,
+            variant,
+        };
+        Ok(Spanned::new(result, self.span_from(start)))
     }
 
-    fn ass_stmt(&mut self) -> Result<Ast, ParseError> {
+    /// Parses the expression after an annotation's keyword, if the keyword is
+    /// next.
+    fn annotation(&mut self, keyword: Token) -> Result<Option<Box<Spanned<Ast>>>, ParseError> {
+        if !self.next_is(keyword) {
+            return Ok(None);
+        }
+
+        self.advance();
+        Ok(Some(Box::new(self.expression()?)))
+    }
+
+    fn ass_stmt(&mut self) -> Result<Spanned<Ast>, ParseError> {
         // <ass_stmt> ::= <ident> ":=" <term>
+        //              | <ident> <arg_list> ( ":=" "[[" <stmt_block> "]]" )?
 
-        // First thing is to get the LHS identifier.
-        let span = match self.advance() {
-            Some(Spanned {
+        // First thing is to get the LHS identifier. Whether this is an
+        // assignment or a definition run isn't known until after it.
+        let checkpoint = self.checkpoint();
+        let span = match self.peek() {
+            Some(&Spanned {
                 inner: Token::Identifier,
                 span,
             }) => span,
-            _ => {
-                return Err(ParseError {
-                    message: "Expected LHS Identifier".to_string(),
-                    span: Span(self.source.len() - 1, self.source.len()),
-                })
-            }
+            _ => return Err(self.unexpected(&[Token::Identifier])),
         };
+        self.advance();
 
         let ident = self.source[span.0..span.1].to_string();
 
+        if self.next_is(Token::LeftParen) {
+            return self.call(checkpoint, ident, span.0);
+        }
+
         if let None
         | Some(Spanned {
-            inner: Token::RightSemantic | Token::Semicolon,
+            inner:
+                Token::Semicolon
+                | Token::RightParen
+                | Token::Else
+                | Token::RightSemantic
+                | Token::LeftBrace
+                | Token::RightBrace
+                | Token::End,
             ..
         }) = self.peek()
         {
-            // If the next token was ]] or ; then this was being used as a definition invocation.
-            // Should be able to be made more general, with all the
-            // other stmt-block terminators.
-            return Ok(Ast::DefinitionRun { ident });
+            // If the statement ends here, at a `;`, anything else that can end a
+            // statement block, or an assertion after it, then this was a
+            // definition invocation.
+            self.builder
+                .start_node_at(checkpoint, NodeKind::DefinitionRun);
+            self.finish_node();
+            return Ok(Spanned::new(Ast::DefinitionRun { ident }, span));
         }
 
+        self.builder.start_node_at(checkpoint, NodeKind::AssStmt);
         self.expect_token(Token::Assign)?;
 
         let rhs = match self.peek() {
@@ -273,56 +962,276 @@ where
                 rhs
             }
 
-            Some(_) => ass_stmt!(ident, self.expression()?),
-            _ => unreachable!(),
+            _ => ass_stmt!(ident, self.expression()?),
         };
 
         // Now we can find the RHS.
-        Ok(rhs)
+        self.finish_node();
+        Ok(Spanned::new(rhs, self.span_from(span.0)))
     }
 
-    fn skip_stmt(&mut self) -> Result<Ast, ParseError> {
+    /// Parses the rest of a call, or of a parameterised definition if the
+    /// arguments are followed by `:=`, after its name.
+    fn call(
+        &mut self,
+        checkpoint: Checkpoint,
+        ident: String,
+        start: usize,
+    ) -> Result<Spanned<Ast>, ParseError> {
+        let (inputs, outputs) = self.arg_list()?;
+
+        if !self.next_is(Token::Assign) {
+            self.builder.start_node_at(checkpoint, NodeKind::Call);
+            self.finish_node();
+
+            let call = Ast::Call {
+                ident,
+                inputs,
+                outputs,
+            };
+            return Ok(Spanned::new(call, self.span_from(start)));
+        }
+
+        let head = self.span_from(start);
+        self.builder.start_node_at(checkpoint, NodeKind::Procedure);
+        self.advance();
+
+        // The arguments were parsed as those of a call, so the inputs have to
+        // be checked to be names.
+        let mut parameters: Vec<String> = Vec::new();
+
+        for input in &inputs {
+            let Ast::Ident(name) = &input.inner else {
+                return Err(ParseError::new(
+                    ParseErrorKind::BadParameter,
+                    "Expected a parameter name, found an expression".to_string(),
+                    input.span,
+                ));
+            };
+            parameters.push(name.clone());
+        }
+
+        let mut names = Vec::new();
+
+        for name in parameters.iter().chain(&outputs) {
+            if names.contains(&name) {
+                return Err(ParseError::new(
+                    ParseErrorKind::BadParameter,
+                    format!("Parameter `{name}` is named more than once"),
+                    head,
+                ));
+            }
+            names.push(name);
+        }
+
+        self.expect_token(Token::LeftSemantic)?;
+        let body = self.stmt_block()?;
+        self.expect_token(Token::RightSemantic)?;
+        self.finish_node();
+
+        let procedure = Ast::Procedure {
+            ident,
+            inputs: parameters,
+            outputs,
+            body: Box::new(body),
+        };
+        Ok(Spanned::new(procedure, self.span_from(start)))
+    }
+
+    /// Parses the arguments of a call: the inputs, then after a `;`, the
+    /// outputs, which are variable names.
+    fn arg_list(&mut self) -> Result<(Vec<Spanned<Ast>>, Vec<String>), ParseError> {
+        // <arg_list> ::= "(" ( <expression> ( "," <expression> )* )?
+        //                ( ";" ( <ident> ( "," <ident> )* )? )? ")"
+
+        self.start_node(NodeKind::ArgList);
+        self.expect_token(Token::LeftParen)?;
+
+        let mut inputs = Vec::new();
+
+        if !self.next_is(Token::Semicolon) && !self.next_is(Token::RightParen) {
+            inputs.push(self.expression()?);
+
+            while self.next_is(Token::Comma) {
+                self.advance();
+                inputs.push(self.expression()?);
+            }
+        }
+
+        let mut outputs = Vec::new();
+
+        if self.next_is(Token::Semicolon) {
+            self.advance();
+
+            if !self.next_is(Token::RightParen) {
+                outputs.push(self.arg_name()?);
+
+                while self.next_is(Token::Comma) {
+                    self.advance();
+                    outputs.push(self.arg_name()?);
+                }
+            }
+        }
+
+        if !self.next_is(Token::RightParen) {
+            let expected: &[Token] = match (inputs.is_empty(), outputs.is_empty()) {
+                (_, false) => &[Token::Comma, Token::RightParen],
+                (true, true) => &[Token::Semicolon, Token::RightParen],
+                (false, true) => &[Token::Comma, Token::Semicolon, Token::RightParen],
+            };
+
+            return Err(self.unexpected(expected));
+        }
+
+        self.advance();
+        self.finish_node();
+        Ok((inputs, outputs))
+    }
+
+    /// Parses the name of an output of a call.
+    fn arg_name(&mut self) -> Result<String, ParseError> {
+        match self.peek() {
+            Some(&Spanned {
+                inner: Token::Identifier,
+                span,
+            }) => {
+                self.advance();
+                Ok(self.source[span.0..span.1].to_string())
+            }
+            _ => Err(self.unexpected(&[Token::Identifier])),
+        }
+    }
+
+    fn skip_stmt(&mut self) -> Result<Spanned<Ast>, ParseError> {
         // <skip_stmt> ::= "skip"
 
+        let start = self.next_start();
+
+        self.start_node(NodeKind::SkipStmt);
         self.expect_token(Token::Skip)?;
-        Ok(Ast::Skip)
+        self.finish_node();
+        Ok(Spanned::new(Ast::Skip, self.span_from(start)))
     }
 
-    fn expression(&mut self) -> Result<Ast, ParseError> {
+    fn check_stmt(&mut self) -> Result<Spanned<Ast>, ParseError> {
+        // <check_stmt> ::= ( "assert" | "assume" ) <expression>
+
+        let start = self.next_start();
+
+        self.start_node(NodeKind::CheckStmt);
+        let assert = self.next_is(Token::Assert);
+        self.advance();
+        let cond = Box::new(self.expression()?);
+        self.finish_node();
+
+        let result = if assert {
+            Ast::Assert { cond }
+        } else {
+            Ast::Assume { cond }
+        };
+        Ok(Spanned::new(result, self.span_from(start)))
+    }
+
+    fn expression(&mut self) -> Result<Spanned<Ast>, ParseError> {
         // <expression> ::= <logical_connective>
 
         self.logical_connective()
     }
 
-    fn logical_connective(&mut self) -> Result<Ast, ParseError> {
-        // <logical_connective> ::= <equality> ( "&" <equality> )*
+    fn logical_connective(&mut self) -> Result<Spanned<Ast>, ParseError> {
+        // <logical_connective> ::= <disjunction> ( "=>" <logical_connective> )?
+        //
+        // Implication is right associative, so `a => b => c` is
+        // `a => (b => c)`.
+
+        let checkpoint = self.checkpoint();
+        let expr = self.disjunction()?;
+
+        if !self.peek().is_some_and(|ti| ti.inner == Token::Implies) {
+            return Ok(expr);
+        }
+
+        self.builder.start_node_at(checkpoint, NodeKind::BinaryExpr);
+        let _operator = self.advance();
+        let rhs = self.logical_connective()?;
+        self.finish_node();
+
+        // a => b == !(a & !b), and the lowered nodes all have the span of the
+        // whole implication.
+        let span = Span(expr.span.0, rhs.span.1);
+        let spanned = |ast| Spanned::new(ast, span);
+
+        Ok(spanned(Ast::Not {
+            expr: Box::new(spanned(and!(expr, spanned(not!(rhs))))),
+            sugar: Some(Sugar::Implies),
+        }))
+    }
+
+    fn disjunction(&mut self) -> Result<Spanned<Ast>, ParseError> {
+        // <disjunction> ::= <conjunction> ( "|" <conjunction> )*
+
+        let checkpoint = self.checkpoint();
+        let mut expr = self.conjunction()?;
+
+        while self.peek().is_some_and(|ti| ti.inner == Token::Or) {
+            self.builder.start_node_at(checkpoint, NodeKind::BinaryExpr);
+            let _operator = self.advance();
+            let rhs = self.conjunction()?;
+            self.finish_node();
+
+            // a | b == !(!a & !b)
+            let span = Span(expr.span.0, rhs.span.1);
+            let spanned = |ast| Spanned::new(ast, span);
+
+            expr = spanned(Ast::Not {
+                expr: Box::new(spanned(and!(spanned(not!(expr)), spanned(not!(rhs))))),
+                sugar: Some(Sugar::Or),
+            });
+        }
+
+        Ok(expr)
+    }
+
+    fn conjunction(&mut self) -> Result<Spanned<Ast>, ParseError> {
+        // <conjunction> ::= <equality> ( "&" <equality> )*
 
+        let checkpoint = self.checkpoint();
         let mut expr = self.equality()?;
 
         while self.peek().is_some_and(|ti| ti.inner == Token::And) {
+            self.builder.start_node_at(checkpoint, NodeKind::BinaryExpr);
             let _operator = self.advance();
             let rhs = self.equality()?;
+            self.finish_node();
 
-            expr = and!(expr, rhs);
+            let span = Span(expr.span.0, rhs.span.1);
+            expr = Spanned::new(and!(expr, rhs), span);
         }
         Ok(expr)
     }
 
-    fn equality(&mut self) -> Result<Ast, ParseError> {
+    fn equality(&mut self) -> Result<Spanned<Ast>, ParseError> {
         // <equality> ::= <comparison> ( ( "=" | "!=" ) <comparison> )?
 
+        let checkpoint = self.checkpoint();
         let mut expr = self.comparison()?;
 
         if self
             .peek()
             .is_some_and(|ti| matches!(ti.inner, Token::Equal | Token::NotEqual))
         {
+            self.builder.start_node_at(checkpoint, NodeKind::BinaryExpr);
             let operator = self.advance();
             let rhs = self.comparison()?;
+            self.finish_node();
+
+            // The lowered nodes all have the span of the whole comparison.
+            let span = Span(expr.span.0, rhs.span.1);
+            let spanned = |ast| Spanned::new(ast, span);
 
             expr = match operator.unwrap().inner {
-                Token::Equal => eq!(expr, rhs),
-                Token::NotEqual => not!(eq!(expr, rhs)),
+                Token::Equal => spanned(eq!(expr, rhs)),
+                Token::NotEqual => spanned(not!(spanned(eq!(expr, rhs)))),
                 _ => unreachable!(),
             }
         }
@@ -330,9 +1239,10 @@ where
         Ok(expr)
     }
 
-    fn comparison(&mut self) -> Result<Ast, ParseError> {
+    fn comparison(&mut self) -> Result<Spanned<Ast>, ParseError> {
         // <comparison> ::= <term> ( ( "<=" | "<" | ">" | ">=" ) <term> )?
 
+        let checkpoint = self.checkpoint();
         let mut expr = self.term()?;
 
         if self.peek().is_some_and(|ti| {
@@ -341,18 +1251,24 @@ where
                 Token::LessEqual | Token::LessThan | Token::GreaterEqual | Token::GreaterThan
             )
         }) {
+            self.builder.start_node_at(checkpoint, NodeKind::BinaryExpr);
             let operator = self.advance().unwrap().inner;
             let rhs = self.term()?;
+            self.finish_node();
+
+            // The lowered nodes all have the span of the whole comparison.
+            let span = Span(expr.span.0, rhs.span.1);
+            let spanned = |ast| Spanned::new(ast, span);
 
             expr = match operator {
                 // a <= b == a <= b
-                Token::LessEqual => less_eq!(expr, rhs),
+                Token::LessEqual => spanned(less_eq!(expr, rhs)),
                 // a < b == !(b <= a)
-                Token::LessThan => not!(less_eq!(rhs, expr)),
+                Token::LessThan => spanned(not!(spanned(less_eq!(rhs, expr)))),
                 // a >= b == (b <= a)
-                Token::GreaterEqual => less_eq!(rhs, expr),
+                Token::GreaterEqual => spanned(less_eq!(rhs, expr)),
                 // a > b == !(a <= b)
-                Token::GreaterThan => not!(less_eq!(expr, rhs)),
+                Token::GreaterThan => spanned(not!(spanned(less_eq!(expr, rhs)))),
 
                 _ => unreachable!(),
             }
@@ -360,94 +1276,137 @@ where
         Ok(expr)
     }
 
-    fn term(&mut self) -> Result<Ast, ParseError> {
+    fn term(&mut self) -> Result<Spanned<Ast>, ParseError> {
         // <term> ::= <factor> ( ( "-" | "+" ) <factor> )*
+        let checkpoint = self.checkpoint();
         let mut expr = self.factor()?;
 
         while self
             .peek()
             .is_some_and(|spanned| matches!(spanned.inner, Token::Subtract | Token::Add))
         {
-            let Spanned {
-                inner: operator,
-                span,
-            } = self.advance().unwrap();
+            self.builder.start_node_at(checkpoint, NodeKind::BinaryExpr);
+            let operator = self.advance().unwrap().inner;
             let right = self.factor()?;
+            self.finish_node();
+
+            let expr_span = Span(expr.span.0, right.span.1);
 
             expr = match operator {
-                Token::Subtract => sub!(expr, right),
-                Token::Add => add!(expr, right),
-                _ => {
-                    return Err(ParseError {
-                        message: "Unexpected operator".to_string(),
-                        span,
-                    })
-                }
+                Token::Subtract => Spanned::new(sub!(expr, right), expr_span),
+                Token::Add => Spanned::new(add!(expr, right), expr_span),
+                _ => unreachable!(),
             };
         }
 
         Ok(expr)
     }
 
-    fn factor(&mut self) -> Result<Ast, ParseError> {
-        // <factor> ::= <unary> ( "*" <unary> )*
+    fn factor(&mut self) -> Result<Spanned<Ast>, ParseError> {
+        // <factor> ::= <unary> ( ( "*" | "/" | "%" ) <unary> )*
+        let checkpoint = self.checkpoint();
         let mut expr = self.unary()?;
 
-        while self.peek().is_some_and(|ti| ti.inner == Token::Multiply) {
-            let _operator = self.advance().unwrap().inner;
+        while self
+            .peek()
+            .is_some_and(|ti| matches!(ti.inner, Token::Multiply | Token::Divide | Token::Modulo))
+        {
+            self.builder.start_node_at(checkpoint, NodeKind::BinaryExpr);
+            let operator = self.advance().unwrap().inner;
             let right = self.unary()?;
+            self.finish_node();
+
+            let span = Span(expr.span.0, right.span.1);
 
-            expr = mul!(expr, right)
+            expr = match operator {
+                Token::Multiply => Spanned::new(mul!(expr, right), span),
+                Token::Divide => Spanned::new(div!(expr, right), span),
+                Token::Modulo => Spanned::new(modulo!(expr, right), span),
+                _ => unreachable!(),
+            };
         }
 
         Ok(expr)
     }
 
-    fn unary(&mut self) -> Result<Ast, ParseError> {
+    fn unary(&mut self) -> Result<Spanned<Ast>, ParseError> {
         // <unary> ::= ( "!" | "-" ) <unary> | <primary>
 
+        let start = self.next_start();
+
         let result = match self.peek() {
             Some(Spanned {
                 inner: Token::Subtract,
-                ..
+                span,
             }) => {
+                // The implicit `0` has the span of the `-`.
+                let zero = Spanned::new(literal!(0), *span);
+
+                self.start_node(NodeKind::UnaryExpr);
                 self.advance();
-                sub!(literal!(0), self.unary()?)
+
+                // A negated literal is range-checked as a negative number, so
+                // that the minimum of the integer type can be written.
+                let operand = match self.peek() {
+                    Some(Spanned {
+                        inner: Token::Literal,
+                        ..
+                    }) => self.signed_primary(true)?,
+                    _ => self.unary()?,
+                };
+
+                let result = sub!(zero, operand);
+                self.finish_node();
+                result
             }
 
             Some(Spanned {
                 inner: Token::Not, ..
             }) => {
+                self.start_node(NodeKind::UnaryExpr);
                 self.advance();
-                not!(self.unary()?)
+                let result = not!(self.unary()?);
+                self.finish_node();
+                result
             }
 
-            _ => self.primary()?,
+            _ => return self.primary(),
         };
 
-        Ok(result)
+        Ok(Spanned::new(result, self.span_from(start)))
+    }
+
+    fn primary(&mut self) -> Result<Spanned<Ast>, ParseError> {
+        self.signed_primary(false)
     }
 
-    fn primary(&mut self) -> Result<Ast, ParseError> {
+    /// Parses a primary expression. If it is a literal and `negated` is set,
+    /// the range check is done on the literal's negation.
+    fn signed_primary(&mut self, negated: bool) -> Result<Spanned<Ast>, ParseError> {
         // <primary> ::=  <ident>
         //              | <literal>
         //              | "(" <expression> ")"
 
-        let Some(Spanned { inner, span }) = self.advance() else {
-            // Some issues may arise if I go past self.source.len(), even though
-            // it may make error annotation some time in the potential future
-            // offset by 1 for this specific issue.
-            return Err(ParseError {
-                message: "Reached end of token stream".to_string(),
-                span: Span(self.source.len() - 1, self.source.len()),
-            })
+        let Some(&Spanned { inner, span }) = self.peek() else {
+            return Err(self.unexpected(EXPRESSION_START));
         };
 
+        // Anything else is an error. The token is left for error recovery.
+        let kind = match inner {
+            Token::LeftParen => NodeKind::ParenExpr,
+            Token::Identifier => NodeKind::Name,
+            Token::Literal | Token::True | Token::False => NodeKind::Literal,
+            _ => return Err(self.unexpected(EXPRESSION_START)),
+        };
+
+        self.start_node(kind);
+        self.advance();
+
         let result = match inner {
             Token::LeftParen => {
                 let expr = self.expression()?;
                 self.expect_token(Token::RightParen)?;
-                expr
+                expr.inner
             }
 
             Token::True => Ast::True,
@@ -460,17 +1419,54 @@ where
 
             Token::Literal => {
                 let literal_str = &self.source[span.0..span.1];
-                Ast::Literal(literal_str.parse().unwrap())
-            }
 
-            _ => {
-                return Err(ParseError {
-                    message: format!("Got unexpected {:?} at the primary parsing stage", inner),
-                    span,
-                })
+                // Literal tokens are only ever made of digits, so they always
+                // parse. They might not fit the integer type, though.
+                let value: Integer = literal_str.parse().unwrap();
+
+                let (checked, sign) = if negated {
+                    (-value.clone(), "-")
+                } else {
+                    (value.clone(), "")
+                };
+
+                if !self.integer_type.contains(&checked) {
+                    return Err(ParseError::new(
+                        ParseErrorKind::LiteralOutOfRange,
+                        format!(
+                            "Integer literal `{sign}{literal_str}` is out of range for {}",
+                            self.integer_type
+                        ),
+                        span,
+                    ));
+                }
+
+                Ast::Literal(value)
             }
+
+            _ => unreachable!(),
         };
 
-        Ok(result)
+        self.finish_node();
+        Ok(Spanned::new(result, self.span_from(span.0)))
+    }
+}
+
+/// The formula of an assertion.
+fn formula(assertion: Spanned<Ast>) -> Box<Spanned<Ast>> {
+    match assertion.inner {
+        Ast::Assertion { formula } => formula,
+        _ => unreachable!(),
+    }
+}
+
+/// The opening bracket that a closing bracket matches.
+fn opener(closer: Token) -> Token {
+    match closer {
+        Token::RightParen => Token::LeftParen,
+        Token::RightSemantic => Token::LeftSemantic,
+        Token::RightBrace => Token::LeftBrace,
+        Token::End => Token::Begin,
+        _ => unreachable!(),
     }
 }
@@ -0,0 +1,40 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// How the bodies of `if` and `while` statements are written.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum BlockStyle {
+    /// The handbook's style, where the last body of an `if` or `while` takes
+    /// every statement after it, so `while b do W; S` runs `S` in the loop.
+    #[default]
+    Greedy,
+    /// Every body is in `{ }` or `begin ... end`, and ends at the closing
+    /// delimiter, so `while b do { W }; S` runs `S` after the loop.
+    Delimited,
+}
+
+impl Display for BlockStyle {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            BlockStyle::Greedy => "greedy",
+            BlockStyle::Delimited => "delimited",
+        };
+
+        write!(f, "{name}")
+    }
+}
+
+/// Parses the names used by the `Display` impl: `greedy` and `delimited`.
+impl FromStr for BlockStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "greedy" => Ok(BlockStyle::Greedy),
+            "delimited" => Ok(BlockStyle::Delimited),
+            _ => Err(format!(
+                "Unknown block style `{s}`; expected `greedy` or `delimited`"
+            )),
+        }
+    }
+}
@@ -0,0 +1,73 @@
+use crate::ast::{AExp, Ast, BExp, Stmt};
+use crate::lexer::Spanned;
+use crate::parser::ParseError;
+use std::fmt::{Display, Formatter};
+
+/// The syntactic categories of the handbook grammar. Every node of the `Ast`
+/// belongs to exactly one of them.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Category {
+    Stmt,
+    AExp,
+    BExp,
+}
+
+impl Category {
+    /// The category that a node belongs to, going by the node alone.
+    pub fn of(ast: &Ast) -> Self {
+        match ast {
+            Ast::Literal(_)
+            | Ast::Ident(_)
+            | Ast::Add { .. }
+            | Ast::Sub { .. }
+            | Ast::Mul { .. }
+            | Ast::Div { .. }
+            | Ast::Mod { .. } => Category::AExp,
+
+            Ast::True
+            | Ast::False
+            | Ast::Not { .. }
+            | Ast::Eq { .. }
+            | Ast::LessEq { .. }
+            | Ast::And { .. } => Category::BExp,
+
+            Ast::Comp { .. }
+            | Ast::Ass { .. }
+            | Ast::Skip
+            | Ast::If { .. }
+            | Ast::While { .. }
+            | Ast::DefinitionRun { .. }
+            | Ast::Procedure { .. }
+            | Ast::Call { .. }
+            | Ast::Block { .. }
+            | Ast::Assert { .. }
+            | Ast::Assume { .. }
+            | Ast::Assertion { .. }
+            | Ast::Triple { .. }
+            | Ast::Error => Category::Stmt,
+        }
+    }
+}
+
+impl Display for Category {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let description = match self {
+            Category::Stmt => "a statement",
+            Category::AExp => "an arithmetic expression",
+            Category::BExp => "a boolean expression",
+        };
+
+        write!(f, "{description}")
+    }
+}
+
+/// Checks that a node, and every node within it, is of the category that its
+/// place in the tree calls for. The error points at the first node that
+/// isn't.
+pub(crate) fn check(ast: &Spanned<Ast>, expected: Category) -> Result<(), ParseError> {
+    match expected {
+        Category::Stmt => Stmt::from_ast(ast).map(drop),
+        Category::AExp => AExp::from_ast(ast).map(drop),
+        Category::BExp => BExp::from_ast(ast).map(drop),
+    }
+}
@@ -1,4 +1,5 @@
-use crate::lexer::Span;
+use crate::lexer::{Span, Token};
+use crate::parser::Category;
 use std::error::Error;
 use std::fmt::{Display, Formatter};
 
@@ -7,10 +8,61 @@ use std::fmt::{Display, Formatter};
 /// error with the token stream.
 #[derive(Debug)]
 pub struct ParseError {
+    pub kind: ParseErrorKind,
     pub message: String,
     pub span: Span,
+    /// The tokens that would have been accepted instead. Empty when no one
+    /// token would have done.
+    pub expected: Vec<Token>,
+    /// The token that was found, or `None` at the end of the token stream.
+    pub found: Option<Token>,
+    /// The likely cause of the error, when it's a common mistake.
+    pub note: Option<String>,
 }
 
+/// The kinds of [`ParseError`], for code that needs to tell them apart.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ParseErrorKind {
+    /// A token that doesn't fit the grammar where it was found.
+    UnexpectedToken,
+    /// The token stream ended while more was expected.
+    UnexpectedEnd,
+    /// An integer literal that doesn't fit the integer type.
+    LiteralOutOfRange,
+    /// Something of the wrong syntactic category, like a boolean expression
+    /// where a number should be.
+    WrongCategory,
+    /// A parameter of a definition that isn't a variable name, or that is
+    /// named twice.
+    BadParameter,
+    /// An `Error` node, left where the parser recovered from another error.
+    Unparsed,
+}
+
+/// The tokens that can start a statement.
+pub(crate) const STATEMENT_START: &[Token] = &[
+    Token::If,
+    Token::While,
+    Token::Identifier,
+    Token::Skip,
+    Token::Assert,
+    Token::Assume,
+    Token::LeftParen,
+    Token::LeftBrace,
+    Token::Begin,
+];
+
+/// The tokens that can start an expression.
+pub(crate) const EXPRESSION_START: &[Token] = &[
+    Token::LeftParen,
+    Token::Identifier,
+    Token::Literal,
+    Token::True,
+    Token::False,
+    Token::Not,
+    Token::Subtract,
+];
+
 impl Error for ParseError {}
 
 impl Display for ParseError {
@@ -20,7 +72,85 @@ impl Display for ParseError {
 }
 
 impl ParseError {
-    pub fn new(message: String, span: Span) -> Self {
-        Self { message, span }
+    pub fn new(kind: ParseErrorKind, message: String, span: Span) -> Self {
+        Self {
+            kind,
+            message,
+            span,
+            expected: Vec::new(),
+            found: None,
+            note: None,
+        }
+    }
+
+    /// Creates an error for finding `found` where one of `expected` should
+    /// have been, like "Expected `)`, found `;`". `found` is `None` at the end
+    /// of the token stream.
+    pub fn unexpected(expected: &[Token], found: Option<Token>, span: Span) -> Self {
+        let found_description = found.map_or("end of input".to_string(), describe);
+
+        let message = if expected.is_empty() {
+            format!("Unexpected {found_description}")
+        } else {
+            format!(
+                "Expected {}, found {found_description}",
+                describe_all(expected)
+            )
+        };
+
+        let kind = match found {
+            Some(_) => ParseErrorKind::UnexpectedToken,
+            None => ParseErrorKind::UnexpectedEnd,
+        };
+
+        Self {
+            expected: expected.to_vec(),
+            found,
+            ..Self::new(kind, message, span)
+        }
+    }
+
+    /// Creates an error for finding something of the wrong syntactic
+    /// category, like "Expected a boolean expression, found an arithmetic
+    /// expression".
+    pub fn wrong_category(expected: Category, found: Category, span: Span) -> Self {
+        let message = format!("Expected {expected}, found {found}");
+        Self::new(ParseErrorKind::WrongCategory, message, span)
+    }
+
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.note = Some(note.into());
+        self
+    }
+}
+
+/// Describes a token the way it's written, like "`:=`", or by what it is when
+/// it can be written many ways, like "an identifier".
+fn describe(token: Token) -> String {
+    match (token, token.spelling()) {
+        (_, Some(spelling)) => format!("`{spelling}`"),
+        (Token::Identifier, None) => "an identifier".to_string(),
+        (Token::Literal, None) => "an integer literal".to_string(),
+        _ => "an unknown token".to_string(),
+    }
+}
+
+/// Describes a set of tokens, like "`then` or `do`". The sets of tokens that
+/// start statements and expressions are described as such.
+fn describe_all(tokens: &[Token]) -> String {
+    if tokens == STATEMENT_START {
+        return "a statement".to_string();
+    }
+
+    if tokens == EXPRESSION_START {
+        return "an expression".to_string();
+    }
+
+    let mut descriptions: Vec<_> = tokens.iter().map(|&token| describe(token)).collect();
+
+    match descriptions.pop() {
+        Some(last) if descriptions.is_empty() => last,
+        Some(last) => format!("{} or {last}", descriptions.join(", ")),
+        None => "nothing".to_string(),
     }
 }
@@ -0,0 +1,280 @@
+//! An interactive read-eval-print loop (REPL) for While.
+//!
+//! The [`Repl`] keeps one [`Interpreter`] alive across inputs, so variables
+//! and definitions persist between them. Inputs starting with `:` are
+//! commands. Anything else is evaluated as an expression if it parses as one,
+//! and otherwise is run as a statement block.
+
+use crate::ast::Ast;
+use crate::interpreter::interpret_error::{InterpretError, InterpretErrorKind};
+use crate::interpreter::scoping::Scoping;
+use crate::interpreter::state::State;
+use crate::interpreter::Interpreter;
+use crate::lexer::{Lexer, Span, Spanned};
+use crate::number::{IntegerType, Rounding};
+use crate::parser::{BlockStyle, ParseError, Parser};
+use crate::skip_stmt;
+use crate::source_navigator::SourceNavigator;
+use std::fs;
+
+const HELP: &str = "\
+Enter a statement to run it, or an expression to evaluate it.
+
+Commands:
+    :state          Print the current state
+    :defs           List the current definitions
+    :reset          Clear all variables and definitions
+    :load <file>    Run a program from a file
+    :help           Print this message
+    :quit           Exit the REPL";
+
+/// What the REPL has to say in response to an input.
+#[derive(Debug, Eq, PartialEq)]
+pub enum Response {
+    /// Regular output, such as the value of an expression or the new state.
+    /// May be empty.
+    Output(String),
+    /// A lexing, parsing or interpreting error, or a bad command.
+    Error(String),
+    /// The user asked to leave the REPL.
+    Quit,
+}
+
+pub struct Repl {
+    interpreter: Interpreter,
+    integer_type: IntegerType,
+    rounding: Rounding,
+    check_annotations: bool,
+    scoping: Scoping,
+    block_style: BlockStyle,
+}
+
+impl Repl {
+    pub fn new() -> Self {
+        Self::with_state(State::new())
+    }
+
+    /// Creates a REPL whose session starts from the given state.
+    pub fn with_state(state: State) -> Self {
+        Self {
+            interpreter: Interpreter::with_state(skip_stmt!().into(), state),
+            integer_type: IntegerType::default(),
+            rounding: Rounding::default(),
+            check_annotations: false,
+            scoping: Scoping::default(),
+            block_style: BlockStyle::default(),
+        }
+    }
+
+    /// Sets the range of integers that inputs may use, for both literals and
+    /// arithmetic.
+    pub fn set_integer_type(&mut self, integer_type: IntegerType) {
+        self.integer_type = integer_type;
+        self.interpreter.set_integer_type(integer_type);
+    }
+
+    /// Sets how `/` and `%` round a quotient that isn't whole.
+    pub fn set_rounding(&mut self, rounding: Rounding) {
+        self.rounding = rounding;
+        self.interpreter.set_rounding(rounding);
+    }
+
+    /// Sets whether loop invariants and variants are checked as inputs run.
+    pub fn set_check_annotations(&mut self, check_annotations: bool) {
+        self.check_annotations = check_annotations;
+        self.interpreter.set_check_annotations(check_annotations);
+    }
+
+    /// Sets which definitions the body of a definition sees when it runs.
+    pub fn set_scoping(&mut self, scoping: Scoping) {
+        self.scoping = scoping;
+        self.interpreter.set_scoping(scoping);
+    }
+
+    /// Sets how the bodies of `if` and `while` statements are delimited in
+    /// inputs.
+    pub fn set_block_style(&mut self, block_style: BlockStyle) {
+        self.block_style = block_style;
+    }
+
+    /// Handles one input, which is either a command or some While source.
+    pub fn handle(&mut self, input: &str) -> Response {
+        let input = input.trim();
+
+        if input.is_empty() {
+            return Response::Output(String::new());
+        }
+
+        match input.strip_prefix(':') {
+            Some(command) => self.command(command),
+            None => self.run_source(input, true),
+        }
+    }
+
+    fn command(&mut self, command: &str) -> Response {
+        let (name, argument) = match command.split_once(char::is_whitespace) {
+            Some((name, argument)) => (name, argument.trim()),
+            None => (command, ""),
+        };
+
+        match name {
+            "state" => Response::Output(self.interpreter.context().state.to_string()),
+
+            "defs" => {
+                let mut names: Vec<_> = self.interpreter.context().definitions.keys().collect();
+                names.sort();
+
+                if names.is_empty() {
+                    Response::Output("No definitions.".to_string())
+                } else {
+                    Response::Output(
+                        names
+                            .into_iter()
+                            .map(String::as_str)
+                            .collect::<Vec<_>>()
+                            .join("\n"),
+                    )
+                }
+            }
+
+            "reset" => {
+                self.interpreter = Interpreter::new(skip_stmt!().into());
+                self.interpreter.set_integer_type(self.integer_type);
+                self.interpreter.set_rounding(self.rounding);
+                self.interpreter
+                    .set_check_annotations(self.check_annotations);
+                self.interpreter.set_scoping(self.scoping);
+                Response::Output("Cleared all variables and definitions.".to_string())
+            }
+
+            "load" if argument.is_empty() => {
+                Response::Error("`:load` needs a file to load".to_string())
+            }
+
+            "load" => match fs::read_to_string(argument) {
+                Ok(source) => self.run_source(&source, false),
+                Err(e) => Response::Error(format!("Failed reading `{argument}`: {e}")),
+            },
+
+            "help" => Response::Output(HELP.to_string()),
+
+            "quit" | "q" => Response::Quit,
+
+            _ => Response::Error(format!(
+                "Unknown command `:{name}`. Type `:help` for a list of commands."
+            )),
+        }
+    }
+
+    /// Lexes, parses and runs some source. If `allow_expressions` is set, the
+    /// source is first tried as an expression, whose value is then shown.
+    fn run_source(&mut self, source: &str, allow_expressions: bool) -> Response {
+        let (tokens, errors) = Lexer::new(source.chars()).lex_recovering();
+
+        if !errors.is_empty() {
+            let annotated: Vec<_> = errors
+                .iter()
+                .map(|e| annotate(source, &e.message, None, e.span))
+                .collect();
+
+            return Response::Error(annotated.join("\n"));
+        }
+
+        let mut expression_error = None;
+
+        if allow_expressions {
+            let mut parser = Parser::new(source, tokens.clone());
+            parser.set_integer_type(self.integer_type);
+            parser.set_block_style(self.block_style);
+
+            match parser.parse_expression() {
+                Ok(expr) => return self.evaluate(expr),
+                Err(e) => expression_error = Some(e),
+            }
+        }
+
+        let mut parser = Parser::new(source, tokens);
+        parser.set_integer_type(self.integer_type);
+        parser.set_block_style(self.block_style);
+
+        let (ast, errors) = parser.parse_recovering();
+
+        if !errors.is_empty() {
+            // Input that gets further as an expression than as a statement,
+            // like `1 +` or `x +`, was meant as an expression, so that error
+            // is the one that says what's wrong with it. Running out of input
+            // is reported at the last token, but counts as getting past it.
+            let progress = |e: &ParseError| (e.span.0, e.found.is_none());
+            let errors = match expression_error {
+                Some(e) if progress(&e) > progress(&errors[0]) => vec![e],
+                _ => errors,
+            };
+
+            let annotated: Vec<_> = errors
+                .iter()
+                .map(|e| annotate(source, &e.message, e.note.as_deref(), e.span))
+                .collect();
+
+            return Response::Error(annotated.join("\n"));
+        }
+
+        self.execute(&ast)
+    }
+
+    /// Runs a statement, responding with the new state.
+    ///
+    /// Unlike lexing and parsing errors, interpreting errors aren't annotated,
+    /// since they can come from a definition given in an earlier input.
+    fn execute(&mut self, statement: &Spanned<Ast>) -> Response {
+        match self.interpreter.execute(statement) {
+            Ok(()) => Response::Output(self.interpreter.context().state.to_string()),
+            Err(InterpretError {
+                kind: InterpretErrorKind::Infeasible,
+                ..
+            }) => Response::Output("infeasible".to_string()),
+            Err(InterpretError {
+                message,
+                state: Some(state),
+                ..
+            }) => Response::Error(format!("{message}\n = note: the state was {state}")),
+            Err(e) => Response::Error(e.to_string()),
+        }
+    }
+
+    fn evaluate(&mut self, expr: Spanned<Ast>) -> Response {
+        // A lone identifier is ambiguous: it could be a variable, or it could
+        // be a definition to run. Definitions win, since variables can always
+        // be inspected with `:state`.
+        if let Ast::Ident(ident) = &expr.inner {
+            if self.interpreter.context().get_definition(ident).is_some() {
+                let run = Ast::DefinitionRun {
+                    ident: ident.clone(),
+                };
+
+                return self.execute(&Spanned::new(run, expr.span));
+            }
+        }
+
+        match self.interpreter.evaluate(&expr) {
+            Ok(value) => Response::Output(value.to_string()),
+            Err(e) => Response::Error(e.to_string()),
+        }
+    }
+}
+
+impl Default for Repl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Formats an error message along with the line of source it points to, and
+/// the error's note if it has one.
+fn annotate(source: &str, message: &str, note: Option<&str>, span: Span) -> String {
+    let annotated = SourceNavigator::new(source).get_annotated_span(span);
+
+    match note {
+        Some(note) => format!("{message}\n{annotated}\n = note: {note}"),
+        None => format!("{message}\n{annotated}"),
+    }
+}
@@ -4,6 +4,10 @@ use std::iter;
 /// Used by error-handling code to find cartesian positions within the source,
 /// such as row and column. This is not needed to find the source tokens corresponding
 /// to a span themselves, but rather their 2D position within a source file
+///
+/// Like [`Span`]s, every index taken or returned by a `SourceNavigator` is a
+/// byte offset, unless stated otherwise. It also converts between these and
+/// char offsets, and gives the UTF-16 columns that editors tend to use.
 pub struct SourceNavigator<'a> {
     source: &'a str,
     line_heads: Vec<usize>,
@@ -13,13 +17,7 @@ impl<'a> SourceNavigator<'a> {
     pub fn new(source: &'a str) -> Self {
         // Line heads are the start indices of each line.
         let line_heads: Vec<_> = iter::once(0)
-            .chain(
-                source
-                    .chars()
-                    .enumerate()
-                    .filter(|(_, c)| c == &'\n')
-                    .map(|(idx, _)| idx + 1),
-            )
+            .chain(source.match_indices('\n').map(|(idx, _)| idx + 1))
             .collect();
 
         Self { source, line_heads }
@@ -27,18 +25,48 @@ impl<'a> SourceNavigator<'a> {
 
     /// Gives the line and column of a particular index.
     pub fn get_position(&self, index: usize) -> FilePos2d {
-        let (this_line_index, (this_start, _)) = self
-            .line_heads
-            .iter()
-            .zip(self.line_heads.iter().skip(1))
-            .enumerate()
-            .find(|(_, (_, next_head))| **next_head >= index)
-            .unwrap();
+        // The line containing `index` is the last one to start at or before it.
+        let row = self.line_heads.partition_point(|&head| head <= index) - 1;
+        let before_index = &self.source[self.line_heads[row]..index];
 
         FilePos2d {
-            row: this_line_index,
-            col: index - this_start,
+            row,
+            col: before_index.chars().count(),
+            utf16_col: before_index.encode_utf16().count(),
+        }
+    }
+
+    /// Gives the index of a position in the source, as given by a line and a
+    /// UTF-16 column. This is how editors tend to describe positions.
+    ///
+    /// Columns past the end of the line give the index of the end of the line.
+    pub fn get_index_from_utf16(&self, row: usize, utf16_col: usize) -> usize {
+        let line_start = self.line_heads[row];
+        let line = self.get_line(row);
+        let mut units = 0;
+
+        for (offset, c) in line.char_indices() {
+            if units >= utf16_col {
+                return line_start + offset;
+            }
+            units += c.len_utf16();
         }
+
+        line_start + line.len()
+    }
+
+    /// Converts a byte index into a char index.
+    pub fn char_index(&self, index: usize) -> usize {
+        self.source[..index].chars().count()
+    }
+
+    /// Converts a char index into a byte index. Char indices past the end of
+    /// the source give the length of the source.
+    pub fn byte_index(&self, char_index: usize) -> usize {
+        self.source
+            .char_indices()
+            .nth(char_index)
+            .map_or(self.source.len(), |(index, _)| index)
     }
 
     /// Given a line number, returns that line in the source stream.
@@ -51,45 +79,40 @@ impl<'a> SourceNavigator<'a> {
             .unwrap_or(&self.source.len())
             .to_owned();
 
-        self.source[this_line_start..next_line_start - 1].trim()
+        self.source[this_line_start..next_line_start].trim_end_matches(['\r', '\n'])
     }
 
     /// Given a span, returns the line containing that span, with the span
     /// content underlined.
     pub fn get_annotated_span(&self, Span(a, b): Span) -> String {
-        let FilePos2d {
-            row: row1,
-            col: col1,
-        } = self.get_position(a);
-
-        let FilePos2d {
-            row: _row2,
-            col: col2,
-        } = self.get_position(b);
-
-        // The big assumption in this function is that the span doesn't cross
-        // lines.
-
-        let line_content = self.get_line(row1);
+        let FilePos2d { row, col, .. } = self.get_position(a);
 
-        let line_start = self.line_heads[row1];
-        let line_end = line_start + line_content.len();
+        let line_content = self.get_line(row);
+        let line_end = self.line_heads[row] + line_content.len();
 
-        let start_to_span = a - line_start;
-        let span_to_end = if line_end < b { 0 } else { line_end - b };
+        // Spans that cross lines are only underlined up to the end of the line
+        // they start on. Empty spans still get a single marker.
+        let span_width = self.source[a..b.min(line_end).max(a)]
+            .chars()
+            .count()
+            .max(1);
 
-        let line_marker = format!("{} | ", row1 + 1);
+        let line_marker = format!("{} | ", row + 1);
 
-        let underline = " ".repeat(start_to_span + line_marker.len())
-            + &"-".repeat(col2 - col1)
-            + &" ".repeat(span_to_end);
+        let underline = " ".repeat(col + line_marker.len()) + &"-".repeat(span_width);
 
         format!("{line_marker}{line_content}\n{underline}")
     }
 }
 
-#[derive(Copy, Clone, Debug)]
+/// A position within the source, as a line and column. Both start from 0.
+///
+/// `col` counts chars, which is what's needed to line things up beneath the
+/// source in a terminal. `utf16_col` counts UTF-16 code units, which is what
+/// most editors expect.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub struct FilePos2d {
-    row: usize,
-    col: usize,
+    pub row: usize,
+    pub col: usize,
+    pub utf16_col: usize,
 }
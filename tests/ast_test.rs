@@ -1,5 +1,11 @@
-use while_tools::ast::Ast;
-use while_tools::{ass_stmt, binary_node, ident, if_stmt, less_eq, literal, skip_stmt, while_stmt};
+use while_tools::ast::{AExp, Ast, BExp, Stmt};
+use while_tools::lexer::{Lexer, Span, Spanned};
+use while_tools::number::Integer;
+use while_tools::parser::{ParseErrorKind, Parser};
+use while_tools::{
+    ass_stmt, binary_node, eq, ident, if_stmt, less_eq, literal, literal_false, literal_true,
+    skip_stmt, while_stmt,
+};
 
 /// Test that the generalised binary node macro works.
 #[test]
@@ -9,8 +15,8 @@ fn test_ast_binary_node_macro() {
     assert_eq!(
         add_node,
         Ast::Add {
-            left: Box::new(Ast::Literal(1)),
-            right: Box::new(Ast::Literal(2))
+            left: Box::new(Ast::Literal(Integer::from(1)).into()),
+            right: Box::new(Ast::Literal(Integer::from(2)).into())
         }
     );
 
@@ -19,8 +25,8 @@ fn test_ast_binary_node_macro() {
     assert_eq!(
         less_eq_node,
         Ast::LessEq {
-            left: Box::new(Ast::Literal(3)),
-            right: Box::new(Ast::Literal(4))
+            left: Box::new(Ast::Literal(Integer::from(3)).into()),
+            right: Box::new(Ast::Literal(Integer::from(4)).into())
         }
     )
 }
@@ -37,10 +43,10 @@ fn test_ast_ident_macro() {
 #[test]
 fn test_ast_literal_macro() {
     let literal_1 = literal!(1);
-    assert_eq!(literal_1, Ast::Literal(1));
+    assert_eq!(literal_1, Ast::Literal(Integer::from(1)));
 
     let literal_negative_5 = literal!(-5);
-    assert_eq!(literal_negative_5, Ast::Literal(-5));
+    assert_eq!(literal_negative_5, Ast::Literal(Integer::from(-5)));
 }
 
 /// Test that ass_stmt! macro correctly produces an Ass node.
@@ -51,7 +57,7 @@ fn test_ast_ass_stmt_macro() {
         ass_x_5,
         Ast::Ass {
             ident: "x".to_string(),
-            value: Box::new(Ast::Literal(5))
+            value: Box::new(Ast::Literal(Integer::from(5)).into())
         }
     );
 }
@@ -67,12 +73,15 @@ fn test_ast_if_macros() {
     assert_eq!(
         if_expansion,
         Ast::If {
-            cond: Box::new(Ast::LessEq {
-                left: Box::new(Ast::Literal(1)),
-                right: Box::new(Ast::Literal(2))
-            }),
-            true_path: Box::new(Ast::Skip),
-            false_path: Box::new(Ast::Skip)
+            cond: Box::new(
+                Ast::LessEq {
+                    left: Box::new(Ast::Literal(Integer::from(1)).into()),
+                    right: Box::new(Ast::Literal(Integer::from(2)).into())
+                }
+                .into()
+            ),
+            true_path: Box::new(Ast::Skip.into()),
+            false_path: Box::new(Ast::Skip.into())
         }
     );
 }
@@ -84,11 +93,16 @@ fn test_ast_while_macros() {
     assert_eq!(
         while_expansion,
         Ast::While {
-            cond: Box::new(Ast::LessEq {
-                left: Box::new(Ast::Literal(1)),
-                right: Box::new(Ast::Literal(2))
-            }),
-            body: Box::new(Ast::Skip),
+            cond: Box::new(
+                Ast::LessEq {
+                    left: Box::new(Ast::Literal(Integer::from(1)).into()),
+                    right: Box::new(Ast::Literal(Integer::from(2)).into())
+                }
+                .into()
+            ),
+            body: Box::new(Ast::Skip.into()),
+            invariant: None,
+            variant: None,
         }
     );
 }
@@ -98,3 +112,110 @@ fn test_ast_while_macros() {
 fn test_ast_skip_macro() {
     assert_eq!(skip_stmt!(), Ast::Skip)
 }
+
+/// Parses the source with error recovery, so that `Error` nodes can be made.
+fn parse(source: &str) -> Spanned<Ast> {
+    let (tokens, _) = Lexer::new(source.chars()).lex_recovering();
+    Parser::new(source, tokens).parse_recovering().0
+}
+
+/// Check that well-formed programs convert to the typed tree, keeping their
+/// spans.
+#[test]
+fn test_typed_conversion() {
+    let stmt = Stmt::from_ast(&parse("W := [[x := 1]]; if x < 2 then W")).unwrap();
+
+    let Stmt::Comp { first, second } = &stmt.inner else {
+        panic!("expected a composition, found {stmt:?}");
+    };
+
+    let Stmt::Define { ident, body } = &first.inner else {
+        panic!("expected a definition, found {first:?}");
+    };
+    assert_eq!(ident, "W");
+    assert_eq!(
+        body.inner,
+        Stmt::Ass {
+            ident: "x".to_string(),
+            value: Box::new(AExp::Literal(Integer::from(1)).into()),
+        }
+    );
+
+    let Stmt::If { cond, .. } = &second.inner else {
+        panic!("expected an if statement, found {second:?}");
+    };
+    assert_eq!(cond.span, Span(20, 25));
+    assert!(matches!(
+        &cond.inner,
+        BExp::Not { expr } if matches!(expr.inner, BExp::LessEq { .. })
+    ));
+}
+
+/// Check that equalities are between numbers, unless the left side is a
+/// boolean.
+#[test]
+fn test_typed_conversion_equality() {
+    let number = BExp::from_ast(&eq!(ident!("x"), literal!(1)).into()).unwrap();
+    assert!(matches!(number.inner, BExp::Eq { .. }));
+
+    let boolean = BExp::from_ast(&eq!(literal_true!(), literal_false!()).into()).unwrap();
+    assert!(matches!(boolean.inner, BExp::BoolEq { .. }));
+}
+
+/// Check that category errors point at the part of the tree that's wrong.
+#[test]
+fn test_typed_conversion_errors() {
+    let error = Stmt::from_ast(&parse("skip; x := true")).unwrap_err();
+    assert_eq!(error.kind, ParseErrorKind::WrongCategory);
+    assert_eq!(error.span, Span(11, 15));
+    assert_eq!(
+        error.message,
+        "Expected an arithmetic expression, found a boolean expression"
+    );
+
+    let error = Stmt::from_ast(&parse("if 3 then skip")).unwrap_err();
+    assert_eq!(error.span, Span(3, 4));
+
+    let error = AExp::from_ast(&parse("x := 1")).unwrap_err();
+    assert_eq!(
+        error.message,
+        "Expected an arithmetic expression, found a statement"
+    );
+
+    let error = Stmt::from_ast(&parse("x := 1; y := * 2")).unwrap_err();
+    assert_eq!(error.kind, ParseErrorKind::Unparsed);
+    assert_eq!(error.span, Span(8, 16));
+}
+
+/// Check that logical variables are the ones that only appear in assertions.
+#[test]
+fn test_logical_variables() {
+    let ast = parse("{x = x0 & y = y0} t := x; x := y; y := t {x = y0 & y = x0 & t = t}");
+    let logical: Vec<_> = ast.inner.logical_variables().into_iter().collect();
+
+    assert_eq!(logical, vec!["x0", "y0"]);
+    assert!(parse("x := 1; {x = 1}")
+        .inner
+        .logical_variables()
+        .is_empty());
+
+    let ast = parse("s := 0; while 1 <= n inv s + n = n0 var n + k do (s := s + 1; n := n - 1)");
+    let logical: Vec<_> = ast.inner.logical_variables().into_iter().collect();
+    assert_eq!(logical, vec!["k", "n0"]);
+}
+
+/// Check that assertions convert to the typed tree, keeping their formulas.
+#[test]
+fn test_typed_conversion_triples() {
+    let stmt = Stmt::from_ast(&parse("{x = x0} x := x + 1 {x0 <= x}")).unwrap();
+
+    let Stmt::Triple { pre, stmt, post } = &stmt.inner else {
+        panic!("expected a triple, found {stmt:?}");
+    };
+    assert!(matches!(pre.inner, BExp::Eq { .. }));
+    assert!(matches!(stmt.inner, Stmt::Ass { .. }));
+    assert!(matches!(post.inner, BExp::LessEq { .. }));
+
+    let stmt = Stmt::from_ast(&parse("{true}")).unwrap();
+    assert!(matches!(stmt.inner, Stmt::Assertion { .. }));
+}
@@ -0,0 +1,378 @@
+use std::io::{ErrorKind, Write};
+use std::process::{Command, Output, Stdio};
+
+/// Runs the `while` binary with the given arguments, feeding `stdin` to it.
+fn run_cli(args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_while"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    // Usage errors exit without reading stdin, which breaks the pipe.
+    if let Err(e) = child.stdin.take().unwrap().write_all(stdin.as_bytes()) {
+        assert_eq!(e.kind(), ErrorKind::BrokenPipe, "{e}");
+    }
+
+    child.wait_with_output().unwrap()
+}
+
+/// Check that `run` reads from stdin and prints the final state.
+#[test]
+fn test_cli_run_stdin() {
+    let output = run_cli(&["run"], "x := 1; y := x + 1");
+
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "[x -> 1, y -> 2]\n"
+    );
+}
+
+/// Check that `-` is treated as stdin.
+#[test]
+fn test_cli_run_dash() {
+    let output = run_cli(&["run", "-"], "x := 3");
+
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "[x -> 3]\n");
+}
+
+/// Check that `run` works on a file.
+#[test]
+fn test_cli_run_file() {
+    let output = run_cli(&["run", "examples/gcd.while"], "");
+
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "[x -> 1, y -> 1, z -> 1]\n"
+    );
+}
+
+/// Check that `check` succeeds on a valid program.
+#[test]
+fn test_cli_check_ok() {
+    let output = run_cli(&["check"], "while x <= 5 do x := x + 1");
+
+    assert!(output.status.success());
+}
+
+/// Check that `lex` prints one token per line.
+#[test]
+fn test_cli_lex() {
+    let output = run_cli(&["lex"], "x := 1");
+
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout).lines().count(), 5);
+}
+
+/// Check that `parse` prints the AST.
+#[test]
+fn test_cli_parse() {
+    let output = run_cli(&["parse"], "skip");
+
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "Skip@0..4\n");
+}
+
+/// Check that lexing errors give a non-zero exit code.
+#[test]
+fn test_cli_lex_error_exit_code() {
+    let output = run_cli(&["check"], "x : 1");
+
+    assert_eq!(output.status.code(), Some(1));
+}
+
+/// Check that parsing errors give a non-zero exit code, and are annotated.
+#[test]
+fn test_cli_parse_error_exit_code() {
+    let output = run_cli(&["check"], "if x then");
+
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("1 | if x then"));
+}
+
+/// Check that parsing an empty program reports an error rather than panicking.
+#[test]
+fn test_cli_empty_program() {
+    let output = run_cli(&["check"], "");
+
+    assert_eq!(output.status.code(), Some(2));
+}
+
+/// Check that interpreting errors give a non-zero exit code.
+#[test]
+fn test_cli_interpret_error_exit_code() {
+    let output = run_cli(&["run"], "W");
+
+    assert_eq!(output.status.code(), Some(3));
+}
+
+/// Check that bad usage gives a non-zero exit code.
+#[test]
+fn test_cli_usage_error() {
+    assert_eq!(run_cli(&[], "").status.code(), Some(64));
+    assert_eq!(run_cli(&["frobnicate"], "").status.code(), Some(64));
+}
+
+/// Check that a missing file gives a non-zero exit code.
+#[test]
+fn test_cli_missing_file() {
+    let output = run_cli(&["run", "examples/does_not_exist.while"], "");
+
+    assert_eq!(output.status.code(), Some(74));
+}
+
+/// Check that `--set` and `--state` give the initial state of a run.
+#[test]
+fn test_cli_initial_state() {
+    let output = run_cli(
+        &[
+            "run",
+            "--state",
+            "[x -> 1, y -> 2]",
+            "--set",
+            "y=3",
+            "--set",
+            "z=-4",
+        ],
+        "w := x + y + z",
+    );
+
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "[w -> 0, x -> 1, y -> 3, z -> -4]\n"
+    );
+}
+
+/// Check that malformed `--set` arguments are usage errors.
+#[test]
+fn test_cli_bad_set() {
+    assert_eq!(run_cli(&["run", "--set", "x"], "").status.code(), Some(64));
+    assert_eq!(
+        run_cli(&["run", "--set", "x=a"], "").status.code(),
+        Some(64)
+    );
+    assert_eq!(run_cli(&["run", "--set"], "").status.code(), Some(64));
+    assert_eq!(
+        run_cli(&["run", "--set", "a=1, b -> 2"], "").status.code(),
+        Some(64)
+    );
+    assert_eq!(
+        run_cli(&["run", "--set", "a -> 1, b=2"], "").status.code(),
+        Some(64)
+    );
+}
+
+/// Check that every lexing error is reported, not just the first.
+#[test]
+fn test_cli_reports_all_lex_errors() {
+    let output = run_cli(&["check"], "x : 1; y := $$\nz := [");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(stderr.matches("error:").count(), 3);
+    assert!(stderr.contains("Unknown characters `$$`"));
+}
+
+/// Check that `--int` chooses the integer type for both literals and
+/// arithmetic.
+#[test]
+fn test_cli_integer_type() {
+    assert_eq!(run_cli(&["run"], "x := 3000000000").status.code(), Some(2));
+    assert_eq!(
+        run_cli(&["run"], "x := 2147483647 + 1").status.code(),
+        Some(3)
+    );
+
+    let output = run_cli(&["run", "--int", "i64"], "x := 3000000000 * 3");
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "[x -> 9000000000]\n"
+    );
+
+    assert_eq!(run_cli(&["run", "--int", "u8"], "").status.code(), Some(64));
+}
+
+/// Check that interpreting errors are shown with the source they came from.
+#[test]
+fn test_cli_interpret_error_location() {
+    let output = run_cli(&["run"], "x := 1;\ny := x + true");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert_eq!(output.status.code(), Some(3));
+    assert!(stderr.contains("<stdin>:2:10"), "{stderr}");
+    assert!(stderr.contains("2 | y := x + true"), "{stderr}");
+}
+
+/// Check that every parsing error is reported, not just the first.
+#[test]
+fn test_cli_reports_all_parse_errors() {
+    let output = run_cli(&["check"], "x := 1 +\ny := 2\nz := * 3\nw := )");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert_eq!(output.status.code(), Some(2));
+    assert_eq!(stderr.matches("error:").count(), 3, "{stderr}");
+}
+
+/// Check that parsing errors use source spellings, and come with a note on
+/// their likely cause.
+#[test]
+fn test_cli_parse_error_notes() {
+    let output = run_cli(&["check"], "if x = 1 then y := 1;; else y := 2");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert_eq!(output.status.code(), Some(2));
+    assert!(
+        stderr.contains("error: Expected a statement, found `;`"),
+        "{stderr}"
+    );
+    assert!(stderr.contains(" = note: two `;`s in a row"), "{stderr}");
+}
+
+/// Check that the rounding of division can be chosen.
+#[test]
+fn test_cli_rounding() {
+    let output = run_cli(&["run"], "x := -7 % 2");
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "[x -> -1]\n");
+
+    let output = run_cli(&["run", "--rounding", "euclidean"], "x := -7 % 2");
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "[x -> 1]\n");
+
+    let output = run_cli(&["run", "--rounding", "floor"], "x := 1");
+    assert_eq!(output.status.code(), Some(64));
+}
+
+/// Check that `fmt` converts between block styles, and that `--blocks` sets
+/// the style that programs are parsed in.
+#[test]
+fn test_cli_fmt_block_styles() {
+    let output = run_cli(
+        &["fmt", "--to", "delimited"],
+        "while x <= 1 do x := x + 1; y := 2",
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "while x <= 1 do {\n    x := x + 1;\n    y := 2\n}\n"
+    );
+
+    let output = run_cli(
+        &["fmt", "--blocks", "delimited", "--to", "greedy"],
+        "while x <= 1 do { x := x + 1 }; y := 2",
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "(while x <= 1 do\n    x := x + 1\n);\ny := 2\n"
+    );
+
+    let output = run_cli(
+        &["run", "--blocks", "delimited"],
+        "while x <= 1 do { x := x + 1 }; y := x",
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "[x -> 2, y -> 2]\n"
+    );
+
+    let output = run_cli(&["check"], "while x <= 1 do { x := x + 1 }");
+    assert_eq!(output.status.code(), Some(2));
+
+    let output = run_cli(&["fmt", "--to", "braces"], "skip");
+    assert_eq!(output.status.code(), Some(64));
+}
+
+/// Check that `lint` warns about greedy blocks, with a suggested fix.
+#[test]
+fn test_cli_lint_greedy_blocks() {
+    let output = run_cli(&["lint"], "while x <= 1 do x := x + 1; y := 2");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert_eq!(output.status.code(), Some(0));
+    assert!(
+        stderr.contains("warning: Every statement after `do` is part of this `while`"),
+        "{stderr}"
+    );
+    assert!(stderr.contains(" = note: if only the first of them is meant to be, write `(while x <= 1 do x := x + 1); y := 2`"), "{stderr}");
+}
+
+/// Check that `--check-annotations` stops a run at a broken loop invariant,
+/// showing the state it was broken in.
+#[test]
+fn test_cli_check_annotations() {
+    let source = "x := 0; while x <= 2 inv x <= 1 do x := x + 1";
+
+    let output = run_cli(&["run"], source);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "[x -> 3]\n");
+
+    let output = run_cli(&["run", "--check-annotations"], source);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert_eq!(output.status.code(), Some(3));
+    assert!(
+        stderr.contains("Loop invariant doesn't hold after iteration 2"),
+        "{stderr}"
+    );
+    assert!(
+        stderr.contains(" = note: the state was [x -> 2]"),
+        "{stderr}"
+    );
+}
+
+/// Check that a failing `assert` is an interpreting error, and that a failing
+/// `assume` quietly ends the run as infeasible.
+#[test]
+fn test_cli_assert_and_assume() {
+    let output = run_cli(&["run"], "x := 3; assert x <= 2");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert_eq!(output.status.code(), Some(3));
+    assert!(
+        stderr.contains("error: Assertion failed: x <= 2"),
+        "{stderr}"
+    );
+    assert!(
+        stderr.contains(" = note: the state was [x -> 3]"),
+        "{stderr}"
+    );
+
+    let output = run_cli(&["run"], "x := 3; assume x <= 2; y := 1");
+
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "infeasible\n");
+    assert!(output.stderr.is_empty());
+}
+
+/// Check that a block's local variables don't show up in the final state.
+#[test]
+fn test_cli_local_blocks() {
+    let output = run_cli(
+        &["run"],
+        "x := 1;\nbegin\n  var x := 2; var y := 3\n  z := x * y\nend",
+    );
+
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "[x -> 1, z -> 6]\n"
+    );
+}
+
+/// Check that `--scoping` chooses which definitions a definition sees.
+#[test]
+fn test_cli_scoping() {
+    let source = "Y := [[x := 1]]; W := [[Y]]; Y := [[x := 2]]; W";
+
+    let output = run_cli(&["run"], source);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "[x -> 2]\n");
+
+    let output = run_cli(&["run", "--scoping", "static"], source);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "[x -> 1]\n");
+
+    let output = run_cli(&["run", "--scoping", "lexical"], source);
+    assert_eq!(output.status.code(), Some(64));
+}
@@ -0,0 +1,210 @@
+use while_tools::cst::{GreenNodeBuilder, NodeKind, SyntaxElement, SyntaxNode};
+use while_tools::lexer::{Lexer, Span, Token};
+use while_tools::parser::*;
+
+/// Parses the source, returning the CST along with whether parsing succeeded.
+fn parse_cst(source: &str) -> (bool, SyntaxNode) {
+    let (tokens, _) = Lexer::new(source.chars()).lex_recovering();
+    let (ast, cst) = Parser::new(source, tokens).parse_with_cst();
+
+    (ast.is_ok(), cst)
+}
+
+/// Check that the CST has exactly the text of the source, trivia and all.
+#[test]
+fn test_cst_is_lossless() {
+    let sources = [
+        "x := 1",
+        "  // leading\n\nx := (1 + 2) * y; /* c */ skip ;\n",
+        "W := [[ x := x - 1 ]];\nwhile ¬(x ≤ 0) do ( W ; y := -y )\r\n",
+        "if x = 1 & !true then (skip) else\n\ty := 2 // trailing",
+    ];
+
+    for source in sources {
+        let (ok, cst) = parse_cst(source);
+
+        assert!(ok, "{source:?} should parse");
+        assert_eq!(cst.text(), source);
+        assert_eq!(cst.span(), Span(0, source.len()));
+    }
+}
+
+/// Check that sources that don't parse, or don't lex, still give a lossless
+/// tree.
+#[test]
+fn test_cst_is_lossless_on_errors() {
+    for source in [
+        "x := (1 + ",
+        "if x then",
+        "x := 1 + * 2",
+        "",
+        "x := $ 1; y := 2",
+        "skip; /*{assume-, begin ",
+    ] {
+        let (ok, cst) = parse_cst(source);
+
+        assert!(!ok, "{source:?} shouldn't parse");
+        assert_eq!(cst.text(), source);
+    }
+}
+
+/// Check the shape of the tree, and that trivia are kept out of the start of
+/// nodes.
+#[test]
+fn test_cst_structure() {
+    let (_, cst) = parse_cst("while (x) < 2 do x := x + 1");
+
+    assert_eq!(
+        format!("{cst:#?}"),
+        r#"Root@0..27
+  StmtBlock@0..27
+    WhileStmt@0..27
+      While@0..5 "while"
+      Whitespace@5..6 " "
+      BinaryExpr@6..13
+        ParenExpr@6..9
+          LeftParen@6..7 "("
+          Name@7..8
+            Identifier@7..8 "x"
+          RightParen@8..9 ")"
+        Whitespace@9..10 " "
+        LessThan@10..11 "<"
+        Whitespace@11..12 " "
+        Literal@12..13
+          Literal@12..13 "2"
+      Whitespace@13..14 " "
+      Do@14..16 "do"
+      Whitespace@16..17 " "
+      StmtBlock@17..27
+        AssStmt@17..27
+          Identifier@17..18 "x"
+          Whitespace@18..19 " "
+          Assign@19..21 ":="
+          Whitespace@21..22 " "
+          BinaryExpr@22..27
+            Name@22..23
+              Identifier@22..23 "x"
+            Whitespace@23..24 " "
+            Add@24..25 "+"
+            Whitespace@25..26 " "
+            Literal@26..27
+              Literal@26..27 "1"
+"#
+    );
+}
+
+/// Check that binary expressions nest to the left, like the `Ast` does.
+#[test]
+fn test_cst_binary_expr_nesting() {
+    let (_, cst) = parse_cst("x := 1 - 2 - 3 * 4");
+
+    let binary: Vec<_> = cst
+        .descendants()
+        .into_iter()
+        .filter(|node| node.kind() == NodeKind::BinaryExpr)
+        .map(|node| node.text())
+        .collect();
+
+    assert_eq!(binary, vec!["1 - 2 - 3 * 4", "1 - 2", "3 * 4"]);
+}
+
+/// Check that tokens know their spans and parents.
+#[test]
+fn test_cst_navigation() {
+    let source = "σ := 1; /* ≠ */ skip";
+    let (_, cst) = parse_cst(source);
+
+    let tokens = cst.descendant_tokens();
+    let skip = tokens.iter().find(|t| t.kind() == Token::Skip).unwrap();
+
+    assert_eq!(skip.span(), Span(19, 23));
+    assert_eq!(&source[skip.span().0..skip.span().1], "skip");
+    assert_eq!(skip.parent().kind(), NodeKind::SkipStmt);
+    assert_eq!(
+        skip.parent().parent().map(|node| node.kind()),
+        Some(NodeKind::StmtBlock)
+    );
+
+    let comment = tokens
+        .iter()
+        .find(|t| t.kind() == Token::BlockComment)
+        .unwrap();
+    assert_eq!(comment.text(), "/* ≠ */");
+    assert_eq!(comment.parent().kind(), NodeKind::StmtBlock);
+
+    // Every token follows on directly from the one before it.
+    for pair in tokens.windows(2) {
+        assert_eq!(pair[0].span().1, pair[1].span().0);
+    }
+}
+
+/// Check that definition runs are told apart from assignments.
+#[test]
+fn test_cst_definition_run() {
+    let (_, cst) = parse_cst("W := [[skip]]; W");
+    let block = cst.children().next().unwrap();
+
+    let kinds: Vec<_> = block.children().map(|node| node.kind()).collect();
+    assert_eq!(kinds, vec![NodeKind::AssStmt, NodeKind::DefinitionRun]);
+}
+
+/// Check that parameterised definitions are told apart from calls, and that
+/// both keep their argument lists.
+#[test]
+fn test_cst_procedure_and_call() {
+    let source = "Add(a, b; r) := [[ r := a + b ]];\nAdd( x , 3 ; z )";
+    let (ok, cst) = parse_cst(source);
+    assert!(ok);
+    assert_eq!(cst.text(), source);
+
+    let block = cst.children().next().unwrap();
+    let nodes: Vec<_> = block.children().collect();
+    let kinds: Vec<_> = nodes.iter().map(|node| node.kind()).collect();
+    assert_eq!(kinds, vec![NodeKind::Procedure, NodeKind::Call]);
+
+    let args = nodes[1].children().next().unwrap();
+    assert_eq!(args.kind(), NodeKind::ArgList);
+    assert_eq!(args.text(), "( x , 3 ; z )");
+}
+
+/// Check that trees can be built by hand, including with checkpoints.
+#[test]
+fn test_green_node_builder() {
+    let mut builder = GreenNodeBuilder::new();
+
+    builder.start_node(NodeKind::Literal);
+    let checkpoint = builder.checkpoint();
+    builder.token(Token::Literal, "1");
+    builder.start_node_at(checkpoint, NodeKind::BinaryExpr);
+    builder.token(Token::Add, "+");
+    builder.token(Token::Literal, "2");
+    builder.finish_node();
+
+    // `finish` closes the outer node.
+    let green = builder.finish();
+    assert_eq!(green.to_string(), "1+2");
+    assert_eq!(green.len(), 3);
+
+    let root = SyntaxNode::new_root(green);
+    let children: Vec<_> = root.children_with_tokens().collect();
+
+    assert!(matches!(
+        &children[..],
+        [SyntaxElement::Node(node)] if node.kind() == NodeKind::BinaryExpr && node.span() == Span(0, 3)
+    ));
+}
+
+/// Check that blocks with local variables keep their declarations.
+#[test]
+fn test_cst_local_block() {
+    let (ok, cst) = parse_cst("begin var x := 1;\n  x := x + 1\nend");
+    assert!(ok);
+
+    let block = cst.children().next().unwrap();
+    let local = block.children().next().unwrap();
+    assert_eq!(local.kind(), NodeKind::LocalBlock);
+
+    let kinds: Vec<_> = local.children().map(|node| node.kind()).collect();
+    assert_eq!(kinds, vec![NodeKind::Declaration, NodeKind::StmtBlock]);
+    assert_eq!(local.children().next().unwrap().text(), "var x := 1;\n");
+}
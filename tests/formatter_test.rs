@@ -0,0 +1,121 @@
+use while_tools::ast::Ast;
+use while_tools::formatter::format;
+use while_tools::lexer::Lexer;
+use while_tools::parser::{BlockStyle, Parser};
+
+fn parse(source: &str, style: BlockStyle) -> Ast {
+    let tokens: Vec<_> = Lexer::new(source.chars())
+        .collect::<Result<_, _>>()
+        .unwrap();
+
+    let mut parser = Parser::new(source, tokens);
+    parser.set_block_style(style);
+    parser.parse().unwrap().inner
+}
+
+/// Check that a program is written one statement per line, with the bodies
+/// of `if` and `while` indented.
+#[test]
+fn test_format_greedy() {
+    let source =
+        "x := 0; (while x <= 3 do x := x + 1; if x = 2 then y := x); W := [[a := 1; b := 2]]; W";
+
+    assert_eq!(
+        format(&parse(source, BlockStyle::Greedy), BlockStyle::Greedy),
+        "\
+x := 0;
+(while x <= 3 do
+    x := x + 1;
+    if x = 2 then
+        y := x
+    else
+        skip
+);
+W := [[
+    a := 1;
+    b := 2
+]];
+W
+"
+    );
+}
+
+/// Check that the delimited style puts every body in `{ }`.
+#[test]
+fn test_format_delimited() {
+    let source =
+        "while x <= 3 do begin x := x + 1; if x = 2 then { y := x } else { skip } end; z := 1";
+
+    assert_eq!(
+        format(&parse(source, BlockStyle::Delimited), BlockStyle::Delimited),
+        "\
+while x <= 3 do {
+    x := x + 1;
+    if x = 2 then {
+        y := x
+    } else {
+        skip
+    }
+};
+z := 1
+"
+    );
+}
+
+/// Check that formatting converts between the block styles, keeping the
+/// meaning of the program.
+#[test]
+fn test_format_converts_between_styles() {
+    let sources = [
+        "x := 1; y := x * (2 + 3) - (4 - 5)",
+        "(while x <= 1 do x := x + 1); y := 2",
+        "while x <= 1 do x := x + 1; y := 2",
+        "if x = 1 then (if true then skip else x := 2); y := 1 else while false do skip",
+        "W := [[(if true then skip else skip); y := 2]]; (if x = 1 | y = 2 then W); W",
+        "{x = x0} (while 1 <= x do x := x - 1; y := y + 1) {x <= 0}; {true}",
+        "{x = x0} (x := 1; y := 2) {x = 1}; {true} W {true}",
+        "while 1 <= x inv 0 <= x var x do x := x - 1; y := 2",
+        "Add(a, b; r) := [[r := a; (while 1 <= b do r := r + 1); b := 0]]; Add(x, 2; z)",
+        "Get(; r) := [[if true then r := 1]]; Get(; q); Put(q)",
+        "begin var x := 1; var y := 2; (while x <= y do x := x + 1); z := x end; z := 0",
+        "while true do begin var x := 1; if x = 1 then skip end; y := 1",
+        "(while true inv x = 1 do skip); while false var 1 do skip",
+    ];
+
+    for source in sources {
+        let greedy = parse(source, BlockStyle::Greedy);
+
+        let delimited = format(&greedy, BlockStyle::Delimited);
+        assert_eq!(
+            parse(&delimited, BlockStyle::Delimited),
+            greedy,
+            "{delimited}"
+        );
+
+        let back = format(
+            &parse(&delimited, BlockStyle::Delimited),
+            BlockStyle::Greedy,
+        );
+        assert_eq!(parse(&back, BlockStyle::Greedy), greedy, "{back}");
+        assert_eq!(back, format(&greedy, BlockStyle::Greedy));
+    }
+}
+
+/// Check that negated literals are written back as negations, so that the
+/// smallest integer still parses once it's formatted.
+#[test]
+fn test_format_negated_literals() {
+    let source = "x := -2147483648; y := -7 / 2 - -1; z := 2 * -3";
+    let ast = parse(source, BlockStyle::Greedy);
+    let formatted = format(&ast, BlockStyle::Greedy);
+
+    assert_eq!(
+        formatted,
+        "\
+x := -2147483648;
+y := -7 / 2 - -1;
+z := 2 * -3
+"
+    );
+    assert_eq!(parse(&formatted, BlockStyle::Greedy), ast);
+}
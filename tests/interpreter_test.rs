@@ -1,31 +1,43 @@
+use while_tools::interpreter::interpret_error::{InterpretError, InterpretErrorKind};
+use while_tools::interpreter::scoping::Scoping;
 use while_tools::interpreter::state::State;
 use while_tools::interpreter::Interpreter;
-use while_tools::lexer::Lexer;
+use while_tools::lexer::{Lexer, Span};
+use while_tools::number::{Integer, IntegerType, Rounding};
 use while_tools::parser::*;
 
-/// Almost like a procedural macro to make the pipeline of running a program
-/// more DRY.
-fn get_program_result(source: &str) -> State {
-    let tokens = Lexer::new(source.chars());
-    let result: Vec<_> = match tokens.collect::<Result<Vec<_>, _>>() {
-        Ok(v) => v,
-        Err(e) => {
-            panic!("{e}");
-        }
-    };
+/// The settings a program is parsed and run with.
+#[derive(Default)]
+struct Settings {
+    integer_type: IntegerType,
+    rounding: Rounding,
+    check_annotations: bool,
+    scoping: Scoping,
+}
 
-    let mut parser = Parser::new(source, result);
+/// Almost like a procedural macro to make the pipeline of running a program
+/// more DRY. Lexing and parsing errors panic, but interpreting errors are
+/// returned.
+fn run_program(source: &str, settings: &Settings) -> Result<State, InterpretError> {
+    let tokens: Vec<_> = Lexer::new(source.chars())
+        .collect::<Result<_, _>>()
+        .unwrap_or_else(|e| panic!("{e}"));
 
-    let ast_result = match parser.parse() {
-        Ok(ast) => ast,
-        Err(msg) => {
-            panic!("{msg}");
-        }
-    };
+    let mut parser = Parser::new(source, tokens);
+    parser.set_integer_type(settings.integer_type);
+    let ast = parser.parse().unwrap_or_else(|e| panic!("{e}"));
 
-    let result = Interpreter::new(ast_result).interpret().unwrap();
+    let mut interpreter = Interpreter::new(ast);
+    interpreter.set_integer_type(settings.integer_type);
+    interpreter.set_rounding(settings.rounding);
+    interpreter.set_check_annotations(settings.check_annotations);
+    interpreter.set_scoping(settings.scoping);
+    interpreter.interpret()
+}
 
-    result
+/// Runs a program with the default settings, panicking on any error.
+fn get_program_result(source: &str) -> State {
+    run_program(source, &Settings::default()).unwrap()
 }
 
 /// Check that the interpreter works for if statements.
@@ -36,3 +48,622 @@ fn test_interpret_if() {
     expected_result.set("x".to_string(), 1);
     assert_eq!(result, expected_result)
 }
+
+/// Check that a run can start from a given initial state.
+#[test]
+fn test_interpret_with_state() {
+    let source = "z := x * y";
+    let tokens: Vec<_> = Lexer::new(source.chars()).map(Result::unwrap).collect();
+    let ast = Parser::new(source, tokens).parse().unwrap();
+
+    let mut initial_state = State::new();
+    initial_state.set("x".to_string(), 5);
+    initial_state.set("y".to_string(), 3);
+
+    let result = Interpreter::with_state(ast, initial_state)
+        .interpret()
+        .unwrap();
+
+    assert_eq!(result.to_string(), "[x -> 5, y -> 3, z -> 15]");
+}
+
+/// Check that a state can round-trip through its textual form.
+#[test]
+fn test_state_round_trip() {
+    let state = get_program_result("x := 1; y := 0 - 2; abc := 30");
+    let text = state.to_string();
+
+    assert_eq!(text, "[abc -> 30, x -> 1, y -> -2]");
+    assert_eq!(text.parse::<State>().unwrap(), state);
+}
+
+/// Check that parsing a state is lenient with whitespace, and accepts the
+/// empty state.
+#[test]
+fn test_state_from_str_whitespace() {
+    let mut expected = State::new();
+    expected.set("x".to_string(), 1);
+    expected.set("y".to_string(), 2);
+
+    assert_eq!(" [ x->1 ,y  ->  2 ] ".parse::<State>().unwrap(), expected);
+    assert_eq!("[]".parse::<State>().unwrap(), State::new());
+}
+
+/// Check that malformed states are rejected.
+#[test]
+fn test_state_from_str_errors() {
+    assert!("x -> 1".parse::<State>().is_err());
+    assert!("[x = 1]".parse::<State>().is_err());
+    assert!("[x -> one]".parse::<State>().is_err());
+    assert!("[1x -> 1]".parse::<State>().is_err());
+    assert!("[if -> 1]".parse::<State>().is_err());
+    assert!("[x -> 1,]".parse::<State>().is_err());
+}
+
+/// Check that programs full of multi-byte characters run correctly.
+#[test]
+fn test_interpret_multibyte_source() {
+    let result = get_program_result(
+        "// Counts down to 0 − the handbook's way.\nx := 3 /* 😀 */; y := 0;\nwhile ¬(x ≤ 0) do (x := x − 1; y := y + 2)",
+    );
+
+    assert_eq!(result.to_string(), "[x -> 0, y -> 6]");
+}
+
+/// Check that arithmetic that leaves the integer type's range is an error.
+#[test]
+fn test_interpret_overflow() {
+    let source = "x := 2147483647; x := x + 1";
+
+    let error = run_program(source, &Settings::default()).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Arithmetic overflow: 2147483648 is out of range for i32"
+    );
+
+    let settings = Settings {
+        integer_type: IntegerType::I64,
+        ..Settings::default()
+    };
+    let state = run_program(source, &settings).unwrap();
+    assert_eq!(state.get("x"), Integer::from(2147483648i64));
+}
+
+/// Check that the minimum of each fixed-width type can be written as a literal.
+#[test]
+fn test_interpret_minimum_literals() {
+    let state = run_program("x := -2147483648", &Settings::default()).unwrap();
+    assert_eq!(state.get("x"), Integer::from(i32::MIN as i64));
+
+    let settings = Settings {
+        integer_type: IntegerType::I64,
+        ..Settings::default()
+    };
+    let state = run_program("x := -9223372036854775808", &settings).unwrap();
+    assert_eq!(state.get("x"), Integer::from(i64::MIN));
+}
+
+/// Check that big integers never overflow.
+#[test]
+fn test_interpret_big_factorial() {
+    let source = "n := 30; f := 1; while !(n <= 0) do (f := f * n; n := n - 1)";
+
+    let settings = Settings {
+        integer_type: IntegerType::I64,
+        ..Settings::default()
+    };
+    assert!(run_program(source, &settings).is_err());
+
+    let settings = Settings {
+        integer_type: IntegerType::Big,
+        ..Settings::default()
+    };
+    let state = run_program(source, &settings).unwrap();
+    assert_eq!(
+        state.get("f").to_string(),
+        "265252859812191058636308480000000"
+    );
+}
+
+/// Check that the richer identifier syntax can be used for variables.
+#[test]
+fn test_interpret_rich_identifiers() {
+    let result = get_program_result("x_1 := 2; x' := x_1 * 3; σ := x' + 1; _ := σ");
+
+    assert_eq!(result.to_string(), "[_ -> 7, x' -> 6, x_1 -> 2, σ -> 7]");
+}
+
+/// Check that interpreting errors point at the node that failed.
+#[test]
+fn test_interpret_error_spans() {
+    let run = |source: &str| {
+        let tokens: Vec<_> = Lexer::new(source.chars())
+            .collect::<Result<_, _>>()
+            .unwrap();
+        let ast = Parser::new(source, tokens).parse().unwrap();
+
+        Interpreter::new(ast).interpret().unwrap_err().span
+    };
+
+    assert_eq!(run("x := 1; y := x + true"), Span(17, 21));
+    assert_eq!(run("skip; if 1 + 2 then skip"), Span(9, 14));
+    assert_eq!(run("x := 1;\nW"), Span(8, 9));
+    assert_eq!(run("x := 2147483647 * 2"), Span(5, 19));
+}
+
+/// Check that `|` and `=>` follow their truth tables.
+#[test]
+fn test_interpret_disjunction_and_implication() {
+    let source = "
+        n := 0;
+        (if false | false then n := n + 1);
+        (if false | true then n := n + 10);
+        (if true => false then n := n + 100);
+        (if false => false then n := n + 1000)
+    ";
+
+    assert_eq!(get_program_result(source).get("n"), Integer::from(1010));
+}
+
+/// Check division and remainders, including the handbook's gcd example.
+#[test]
+fn test_interpret_division() {
+    let source = "
+        q := 17 / 5; r := 17 % 5;
+        a := 1071; b := 462;
+        while !(b = 0) do (t := b; b := a % b; a := t)
+    ";
+    let state = get_program_result(source);
+
+    assert_eq!(state.get("q"), Integer::from(3));
+    assert_eq!(state.get("r"), Integer::from(2));
+    assert_eq!(state.get("a"), Integer::from(21));
+}
+
+/// Check that the rounding of `/` and `%` can be chosen.
+#[test]
+fn test_interpret_division_rounding() {
+    let source = "q := -7 / 2; r := -7 % 2";
+
+    let state = get_program_result(source);
+    assert_eq!(
+        (state.get("q"), state.get("r")),
+        (Integer::from(-3), Integer::from(-1))
+    );
+
+    let settings = Settings {
+        rounding: Rounding::Euclidean,
+        ..Settings::default()
+    };
+    let state = run_program(source, &settings).unwrap();
+    assert_eq!(
+        (state.get("q"), state.get("r")),
+        (Integer::from(-4), Integer::from(1))
+    );
+}
+
+/// Check that dividing by zero is an error pointing at the divisor, and that
+/// dividing the smallest integer by -1 overflows.
+#[test]
+fn test_interpret_division_errors() {
+    let error = run_program("x := 0; y := 1 / (x * 2)", &Settings::default()).unwrap_err();
+
+    assert_eq!(error.message, "Division by zero");
+    assert_eq!(error.span, Span(17, 24));
+    assert!(run_program("x := 1 % 0", &Settings::default()).is_err());
+
+    let error = run_program("x := (-2147483647 - 1) / -1", &Settings::default()).unwrap_err();
+    assert!(error.message.contains("overflow"));
+}
+
+/// Check that division errors aren't hidden by the negations that `<`, `>`,
+/// `!=` and `!` are lowered to.
+#[test]
+fn test_interpret_division_errors_in_conditions() {
+    for source in [
+        "if x / 0 < 1 then y := 1",
+        "if x / 0 > 1 then y := 1",
+        "if x % 0 != 1 then y := 1",
+        "if !(x % 0 = 1) then y := 1",
+    ] {
+        let error = run_program(source, &Settings::default()).unwrap_err();
+        assert_eq!(error.message, "Division by zero", "{source}");
+    }
+}
+
+/// Check that assertions are only for reasoning about programs, so running
+/// them does nothing, even when they don't hold.
+#[test]
+fn test_interpret_assertions() {
+    let source = "{x = x0} x := x + 1 {x = x0 + 1}; {false}; y := x";
+    let result = get_program_result(source);
+
+    assert_eq!(result.get("x"), Integer::from(1));
+    assert_eq!(result.get("y"), Integer::from(1));
+    assert_eq!(result.get("x0"), Integer::from(0));
+}
+
+/// Check that a loop whose invariant and variant are right runs as it would
+/// unchecked, and that annotations are ignored unless they're checked.
+#[test]
+fn test_interpret_loop_annotations() {
+    let settings = Settings {
+        check_annotations: true,
+        ..Settings::default()
+    };
+
+    let source = "n := 5; s := 0; i := 0;
+        while i <= n - 1 inv 2 * s = i * (i - 1) & i <= n var n - i do
+            (s := s + i; i := i + 1)";
+    let checked = run_program(source, &settings).unwrap();
+    assert_eq!(checked, get_program_result(source));
+    assert_eq!(checked.get("s"), Integer::from(10));
+
+    let source = "x := 3; while 1 <= x inv false var 0 - x do x := x - 1";
+    assert_eq!(get_program_result(source).get("x"), Integer::from(0));
+}
+
+/// Check that a broken invariant is reported with how many iterations had
+/// finished, and the state it was broken in.
+#[test]
+fn test_interpret_invariant_broken() {
+    let settings = Settings {
+        check_annotations: true,
+        ..Settings::default()
+    };
+
+    let source = "x := 0; while x <= 2 inv x <= 1 do x := x + 1";
+    let error = run_program(source, &settings).unwrap_err();
+
+    assert_eq!(
+        error.kind,
+        InterpretErrorKind::InvariantBroken { iterations: 2 }
+    );
+    assert_eq!(
+        error.message,
+        "Loop invariant doesn't hold after iteration 2"
+    );
+    assert_eq!(error.span, Span(25, 31));
+    assert_eq!(error.state.unwrap().get("x"), Integer::from(2));
+
+    let source = "x := 5; while x <= 2 inv x <= 1 do x := x + 1";
+    let error = run_program(source, &settings).unwrap_err();
+    assert_eq!(
+        error.kind,
+        InterpretErrorKind::InvariantBroken { iterations: 0 }
+    );
+    assert_eq!(
+        error.message,
+        "Loop invariant doesn't hold before the first iteration"
+    );
+}
+
+/// Check that a variant has to be non-negative before every iteration, and
+/// has to decrease in every iteration.
+#[test]
+fn test_interpret_variant_errors() {
+    let settings = Settings {
+        check_annotations: true,
+        ..Settings::default()
+    };
+
+    let source = "x := 0; while x <= 3 var 2 - x do x := x + 1";
+    let error = run_program(source, &settings).unwrap_err();
+
+    assert_eq!(
+        error.kind,
+        InterpretErrorKind::VariantNegative { iterations: 3 }
+    );
+    assert_eq!(
+        error.message,
+        "Loop variant is negative before iteration 4: it is -1"
+    );
+    assert_eq!(error.span, Span(25, 30));
+    assert_eq!(error.state.unwrap().get("x"), Integer::from(3));
+
+    let source = "x := 0; while x <= 3 var 5 do x := x + 1";
+    let error = run_program(source, &settings).unwrap_err();
+
+    assert_eq!(
+        error.kind,
+        InterpretErrorKind::VariantNotDecreasing { iterations: 1 }
+    );
+    assert_eq!(
+        error.message,
+        "Loop variant didn't decrease in iteration 1: it went from 5 to 5"
+    );
+    assert_eq!(error.state.unwrap().get("x"), Integer::from(1));
+}
+
+/// Check that a failing `assert` stops the run with the state it failed in,
+/// and that one that holds does nothing.
+#[test]
+fn test_interpret_assert() {
+    let result = get_program_result("x := 3; assert 1 <= x & x <= 3; y := x");
+    assert_eq!(result.get("y"), Integer::from(3));
+
+    let source = "x := 3; assert x <= 2; y := x";
+    let error = run_program(source, &Settings::default()).unwrap_err();
+
+    assert_eq!(error.kind, InterpretErrorKind::AssertionFailed);
+    assert_eq!(error.message, "Assertion failed: x <= 2");
+    assert_eq!(error.span, Span(15, 21));
+    assert_eq!(error.state.unwrap().to_string(), "[x -> 3]");
+}
+
+/// Check that a failing `assume` ends the run as infeasible.
+#[test]
+fn test_interpret_assume() {
+    let result = get_program_result("x := 3; assume x = 3; y := x");
+    assert_eq!(result.get("y"), Integer::from(3));
+
+    let source = "x := 3; while true do (assume x <= 5; x := x + 1)";
+    let error = run_program(source, &Settings::default()).unwrap_err();
+
+    assert_eq!(error.kind, InterpretErrorKind::Infeasible);
+    assert_eq!(error.state.unwrap().get("x"), Integer::from(6));
+}
+
+/// Check that inputs are passed by value and outputs by result, and that
+/// parameters don't change the caller's variables of the same names.
+#[test]
+fn test_interpret_procedures() {
+    let result = get_program_result("Add(a, b; r) := [[r := a + b]]; x := 2; Add(x, x * 3; z)");
+    assert_eq!(result.to_string(), "[x -> 2, z -> 8]");
+
+    // Changing an input doesn't change the argument, and an output starts
+    // unset, whatever the caller had.
+    let result =
+        get_program_result("Inc(a; r) := [[r := r + 1; a := 0]]; a := 5; r := 10; Inc(a; r)");
+    assert_eq!(result.to_string(), "[a -> 5, r -> 1]");
+
+    // Variables that aren't parameters are still shared with the caller.
+    let result = get_program_result("Count(n;) := [[c := c + n]]; Count(2;); Count(3)");
+    assert_eq!(result.to_string(), "[c -> 5]");
+
+    let result = get_program_result(
+        "Fact(n; r) := [[if n <= 0 then r := 1 else (Fact(n - 1; r); r := r * n)]]; Fact(6; f)",
+    );
+    assert_eq!(result.to_string(), "[f -> 720]");
+}
+
+/// Check that running a definition with the wrong number of arguments is an
+/// error.
+#[test]
+fn test_interpret_arity_mismatch() {
+    let source = "Add(a, b; r) := [[r := a + b]]; Add(1; z)";
+    let error = run_program(source, &Settings::default()).unwrap_err();
+
+    assert_eq!(error.kind, InterpretErrorKind::ArityMismatch);
+    assert_eq!(
+        error.message,
+        "Definition Add takes 2 inputs and 1 output, but was given 1 input and 1 output"
+    );
+    assert_eq!(error.span, Span(32, 41));
+
+    for source in [
+        "Add(a, b; r) := [[r := a + b]]; Add",
+        "Add(a, b; r) := [[r := a + b]]; Add(1, 2)",
+        "W := [[skip]]; W(1)",
+    ] {
+        let error = run_program(source, &Settings::default()).unwrap_err();
+        assert_eq!(error.kind, InterpretErrorKind::ArityMismatch, "{source}");
+    }
+
+    assert!(run_program("W := [[skip]]; W()", &Settings::default()).is_ok());
+}
+
+/// Check that a block's local variables shadow those outside of it, and are
+/// put back when it ends, so they don't leak into the final state.
+#[test]
+fn test_interpret_local_blocks() {
+    let result =
+        get_program_result("x := 5; begin var x := 1; var y := x + 1; z := x + y; x := 7 end");
+    assert_eq!(result.to_string(), "[x -> 5, z -> 3]");
+
+    // A declaration's value uses the variable from outside of the block.
+    let result = get_program_result("x := 5; begin var x := x + 1; y := x end");
+    assert_eq!(result.to_string(), "[x -> 5, y -> 6]");
+
+    let result = get_program_result(
+        "x := 1; begin var x := 2; begin var x := 3; a := x end; b := x end; c := x",
+    );
+    assert_eq!(result.to_string(), "[a -> 3, b -> 2, c -> 1, x -> 1]");
+
+    let result = get_program_result("begin var x := 1; var x := 2; y := x end");
+    assert_eq!(result.to_string(), "[y -> 2]");
+
+    // Blocks run again on every iteration, starting afresh.
+    let result = get_program_result(
+        "n := 0; while n <= 2 do (begin var t := t + n; s := s + t end; n := n + 1)",
+    );
+    assert_eq!(result.to_string(), "[n -> 3, s -> 3]");
+}
+
+/// Check that a block's local variables are put back even when it stops
+/// with an error.
+#[test]
+fn test_interpret_local_block_errors() {
+    let source = "x := 1; begin var x := 2; assert x = 1 end";
+    let error = run_program(source, &Settings::default()).unwrap_err();
+
+    assert_eq!(error.kind, InterpretErrorKind::AssertionFailed);
+    assert_eq!(error.state.unwrap().to_string(), "[x -> 2]");
+
+    let mut interpreter = {
+        let tokens: Vec<_> = Lexer::new(source.chars())
+            .collect::<Result<_, _>>()
+            .unwrap();
+        Interpreter::new(Parser::new(source, tokens).parse().unwrap())
+    };
+    assert!(interpreter.interpret().is_err());
+    assert_eq!(interpreter.context().state.to_string(), "[x -> 1]");
+}
+
+/// Check the textbook's example of static and dynamic scope for procedures,
+/// where `Q` runs `P`, which is redefined before `Q` runs. Under dynamic
+/// scoping, `Q` runs the new `P`, giving `y = 6`, and under static scoping it
+/// runs the `P` from when it was defined, giving `y = 10`. Variables are
+/// dynamically scoped either way.
+#[test]
+fn test_interpret_scoping_textbook_example() {
+    let static_scoping = Settings {
+        scoping: Scoping::Static,
+        ..Settings::default()
+    };
+
+    let source = "
+        begin var x := 0;
+            P := [[x := x * 2]];
+            Q := [[P]];
+            begin var x := 5;
+                P := [[x := x + 1]];
+                Q; y := x
+            end
+        end";
+
+    let dynamic = run_program(source, &Settings::default()).unwrap();
+    assert_eq!(dynamic.to_string(), "[y -> 6]");
+
+    let fixed = run_program(source, &static_scoping).unwrap();
+    assert_eq!(fixed.to_string(), "[y -> 10]");
+
+    assert_eq!(get_program_result(source), dynamic);
+}
+
+/// Check that a definition only sees the definitions from before it under
+/// static scoping, apart from itself, so that it can still be recursive.
+#[test]
+fn test_interpret_static_scoping() {
+    let static_scoping = Settings {
+        scoping: Scoping::Static,
+        ..Settings::default()
+    };
+
+    // A definition can refer to one that's only defined later on.
+    let source = "W := [[Y]]; Y := [[x := 1]]; W";
+    assert!(run_program(source, &Settings::default()).is_ok());
+
+    let error = run_program(source, &static_scoping).unwrap_err();
+    assert_eq!(error.message, "Definition Y not defined yet");
+
+    // Redefining `Y` changes what `W` does.
+    let source = "Y := [[x := 1]]; W := [[Y]]; Y := [[x := 2]]; W";
+    let state = run_program(source, &Settings::default()).unwrap();
+    assert_eq!(state.get("x"), Integer::from(2));
+    let state = run_program(source, &static_scoping).unwrap();
+    assert_eq!(state.get("x"), Integer::from(1));
+
+    // The textbook's factorial, which is recursive.
+    let source = "
+        Fac := [[begin var z := x;
+            if x = 1 then skip
+            else (x := x - 1; Fac; y := z * y)
+        end]];
+        x := 5; y := 1; Fac";
+    for scoping in [Scoping::Dynamic, Scoping::Static] {
+        let settings = Settings {
+            scoping,
+            ..Settings::default()
+        };
+        let state = run_program(source, &settings).unwrap();
+        assert_eq!(state.get("y"), Integer::from(120), "{scoping}");
+    }
+
+    // Definitions with parameters capture their definitions too.
+    let source = "Inc(a; r) := [[r := a + 1]]; Twice(a; r) := [[Inc(a; r); Inc(r; r)]];
+        Inc(a; r) := [[r := a * 10]]; Twice(1; z)";
+    let state = run_program(source, &Settings::default()).unwrap();
+    assert_eq!(state.get("z"), Integer::from(100));
+    let state = run_program(source, &static_scoping).unwrap();
+    assert_eq!(state.get("z"), Integer::from(3));
+}
+
+/// Check that definitions made in a block are local to it, and that the ones
+/// from outside of it are back once it ends.
+#[test]
+fn test_interpret_local_definitions() {
+    let static_scoping = Settings {
+        scoping: Scoping::Static,
+        ..Settings::default()
+    };
+
+    for settings in [Settings::default(), static_scoping] {
+        let source = "begin var x := 0; W := [[x := 1]] end; W";
+        let error = run_program(source, &settings).unwrap_err();
+        assert_eq!(error.message, "Definition W not defined yet");
+
+        let source = "W := [[x := 1]]; begin var y := 0; W := [[x := 2]]; W end; z := x; W";
+        let state = run_program(source, &settings).unwrap();
+        assert_eq!(state.to_string(), "[x -> 1, z -> 2]");
+    }
+}
+
+/// Check local definitions where the two scopings differ. `Q` is defined in
+/// a block before `P` is redefined there, so `Q` only runs the new `P` under
+/// dynamic scoping. Once the block ends, both are back to running the first
+/// `P`.
+#[test]
+fn test_interpret_scoping_local_definitions() {
+    let static_scoping = Settings {
+        scoping: Scoping::Static,
+        ..Settings::default()
+    };
+
+    let source = "
+        P := [[x := x + 1]];
+        begin var x := 10;
+            Q := [[P; P]];
+            P := [[x := x * 3]];
+            Q; y := x
+        end;
+        Q := [[P]];
+        Q; z := x";
+
+    let state = run_program(source, &Settings::default()).unwrap();
+    assert_eq!(state.to_string(), "[x -> 1, y -> 90, z -> 1]");
+
+    let state = run_program(source, &static_scoping).unwrap();
+    assert_eq!(state.to_string(), "[x -> 1, y -> 12, z -> 1]");
+
+    // The textbook's example with the recursive factorial declared locally,
+    // running a `P` that's redefined in an inner block.
+    let source = "
+        begin var r := 0;
+            P := [[r := r + 1]];
+            Fac := [[begin var z := x;
+                if x = 1 then P
+                else (x := x - 1; Fac; y := z * y)
+            end]];
+            begin var r := 100;
+                P := [[r := r + 2]];
+                x := 4; y := 1; Fac; s := r
+            end
+        end";
+
+    let state = run_program(source, &Settings::default()).unwrap();
+    assert_eq!(state.to_string(), "[s -> 102, x -> 1, y -> 24]");
+
+    let state = run_program(source, &static_scoping).unwrap();
+    assert_eq!(state.to_string(), "[s -> 101, x -> 1, y -> 24]");
+}
+
+/// Check that capturing definitions under static scoping doesn't copy the
+/// environments that they captured in turn, which would take exponential time
+/// in the number of definitions.
+#[test]
+fn test_interpret_static_scoping_many_definitions() {
+    let mut source = "D0 := [[x := x + 1]];".to_string();
+
+    for i in 1..=40 {
+        source.push_str(&format!("D{i} := [[D{}; x := x + 1]];", i - 1));
+    }
+    source.push_str("D40");
+
+    let settings = Settings {
+        scoping: Scoping::Static,
+        ..Settings::default()
+    };
+    let state = run_program(&source, &settings).unwrap();
+    assert_eq!(state.get("x"), Integer::from(41));
+}
@@ -23,20 +23,20 @@ fn test_basic_lexing() {
 /// returned when encountering an unknown character in the stream.
 #[test]
 fn test_err_variant() {
-    let lexer = Lexer::new("x : 1;|".chars());
+    let lexer = Lexer::new("x : 1;?".chars());
     let tokens: Vec<_> = lexer.collect();
 
     // The lexer should return a ParseError at `:`.
     assert!(tokens[2].is_err());
 
-    // The lexer should return a ParseError at `|`.
+    // The lexer should return a ParseError at `?`.
     assert!(tokens[6].is_err());
 }
 
 /// Check that the spans returned within the ParseError are correct.
 #[test]
 fn test_parse_error_spans() {
-    let lexer = Lexer::new("x : 1;|".chars());
+    let lexer = Lexer::new("x : 1;?".chars());
     let tokens: Vec<_> = lexer.collect();
 
     // The lexer should return a ParseError at `:`.
@@ -46,7 +46,7 @@ fn test_parse_error_spans() {
         .as_ref()
         .is_err_and(|err| err.span == Span(2, 3)));
 
-    // The lexer should return a ParseError at `|`.
+    // The lexer should return a ParseError at `?`.
     assert!(tokens
         .get(6)
         .unwrap()
@@ -122,3 +122,277 @@ fn test_asi_non_crlf_endings() {
 
     assert_eq!(tokens, vec![Token::Skip, Token::Semicolon, Token::Skip]);
 }
+
+/// Test that line comments run up to, but not including, the line break.
+#[test]
+fn test_line_comment() {
+    let lexer = Lexer::new("skip // a comment\nskip".chars());
+    let tokens: Vec<_> = lexer.map(|result| result.unwrap()).collect();
+
+    assert_eq!(
+        tokens.iter().map(|t| t.inner).collect::<Vec<_>>(),
+        vec![
+            Token::Skip,
+            Token::Whitespace,
+            Token::LineComment,
+            Token::Semicolon,
+            Token::Skip
+        ]
+    );
+    assert_eq!(tokens[2].span, Span(5, 17));
+}
+
+/// Test that block comments can be nested, and span all their lines.
+#[test]
+fn test_nested_block_comment() {
+    let lexer = Lexer::new("skip /* a /* nested\n */ comment */ skip".chars());
+    let tokens: Vec<_> = lexer.map(|result| result.unwrap()).collect();
+
+    assert_eq!(
+        tokens.iter().map(|t| t.inner).collect::<Vec<_>>(),
+        vec![
+            Token::Skip,
+            Token::Whitespace,
+            Token::BlockComment,
+            Token::Whitespace,
+            Token::Skip
+        ]
+    );
+    assert_eq!(tokens[2].span, Span(5, 34));
+}
+
+/// Test that an unterminated block comment is an error pointing at its opening
+/// delimiter.
+#[test]
+fn test_unterminated_block_comment() {
+    let lexer = Lexer::new("skip /* a /* nested */ comment".chars());
+    let tokens: Vec<_> = lexer.collect();
+
+    assert!(tokens
+        .last()
+        .unwrap()
+        .as_ref()
+        .is_err_and(|err| err.span == Span(5, 7)));
+}
+
+/// Test that, when lexing recovers from an unterminated block comment, its
+/// token runs to the end of the source, while the error only points at the
+/// `/*`.
+#[test]
+fn test_lex_recovering_unterminated_block_comment() {
+    let (tokens, errors) = Lexer::new("skip /* x := 1; y".chars()).lex_recovering();
+
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].span, Span(5, 7));
+    assert_eq!(tokens.last().unwrap().inner, Token::Unknown);
+    assert_eq!(tokens.last().unwrap().span, Span(5, 17));
+}
+
+/// Test that comments are trivia, and so are skipped by the parser.
+#[test]
+fn test_comments_are_trivia() {
+    assert!(Token::LineComment.is_trivia());
+    assert!(Token::BlockComment.is_trivia());
+    assert!(Token::Whitespace.is_trivia());
+    assert!(!Token::Semicolon.is_trivia());
+}
+
+/// Test that the handbook's Unicode operators lex as the same tokens as their
+/// ASCII forms.
+#[test]
+fn test_unicode_operators() {
+    let unicode: Vec<_> = Lexer::new("− ∧ ∨ ⇒ ¬ ≤ ≥ ≠ ≜ ⟦ ⟧".chars())
+        .map(|result| result.unwrap().inner)
+        .collect();
+
+    let ascii: Vec<_> = Lexer::new("- & | => ! <= >= != := [[ ]]".chars())
+        .map(|result| result.unwrap().inner)
+        .collect();
+
+    assert_eq!(unicode, ascii);
+}
+
+/// Test that spans are measured in bytes, so that they slice the source
+/// correctly even after multi-byte characters.
+#[test]
+fn test_spans_after_multibyte_characters() {
+    let source = "/* σ → 😀 */ x := y − 1";
+    let tokens: Vec<_> = Lexer::new(source.chars())
+        .map(|result| result.unwrap())
+        .filter(|token| !token.inner.is_trivia())
+        .collect();
+
+    let texts: Vec<_> = tokens
+        .iter()
+        .map(|token| &source[token.span.0..token.span.1])
+        .collect();
+
+    assert_eq!(texts, vec!["x", ":=", "y", "−", "1"]);
+    assert_eq!(tokens[3].span, Span(25, 28));
+}
+
+/// Test that the recovering mode returns every token and every error, rather
+/// than stopping at the first error.
+#[test]
+fn test_lex_recovering() {
+    let (tokens, errors) = Lexer::new("x : 1; y := 2 ]".chars()).lex_recovering();
+
+    assert_eq!(
+        tokens.iter().map(|t| t.inner).collect::<Vec<_>>(),
+        vec![
+            Token::Identifier,
+            Token::Whitespace,
+            Token::Unknown,
+            Token::Whitespace,
+            Token::Literal,
+            Token::Semicolon,
+            Token::Whitespace,
+            Token::Identifier,
+            Token::Whitespace,
+            Token::Assign,
+            Token::Whitespace,
+            Token::Literal,
+            Token::Whitespace,
+            Token::Unknown,
+        ]
+    );
+
+    assert_eq!(
+        errors.iter().map(|e| e.span).collect::<Vec<_>>(),
+        vec![Span(2, 3), Span(14, 15)]
+    );
+}
+
+/// Test that the recovering mode merges runs of unknown characters into one
+/// token and one error.
+#[test]
+fn test_lex_recovering_merges_unknown_runs() {
+    let (tokens, errors) = Lexer::new("x := $#@ + 1 ? 2".chars()).lex_recovering();
+
+    assert_eq!(errors.len(), 2);
+
+    assert_eq!(
+        errors[0].kind,
+        LexErrorKind::UnknownCharacters("$#@".to_string())
+    );
+    assert_eq!(errors[0].span, Span(5, 8));
+    assert_eq!(errors[0].message, "Unknown characters `$#@`");
+
+    assert_eq!(errors[1].message, "Unknown character `?`");
+
+    let unknowns: Vec<_> = tokens
+        .iter()
+        .filter(|t| t.inner == Token::Unknown)
+        .map(|t| t.span)
+        .collect();
+
+    assert_eq!(unknowns, vec![Span(5, 8), Span(13, 14)]);
+}
+
+/// Test that incomplete operators aren't merged with unknown characters, and
+/// that they get specific messages.
+#[test]
+fn test_lex_error_messages() {
+    let (_, errors) = Lexer::new("$: [ ]".chars()).lex_recovering();
+
+    let messages: Vec<_> = errors.iter().map(|e| e.message.as_str()).collect();
+
+    assert_eq!(
+        messages,
+        vec![
+            "Unknown character `$`",
+            "`:` must be followed by `=`",
+            "single `[`; did you mean `[[`?",
+            "single `]`; did you mean `]]`?",
+        ]
+    );
+    assert_eq!(errors[1].kind, LexErrorKind::IncompleteOperator);
+}
+
+/// Test that identifiers can contain underscores, Unicode letters and trailing
+/// primes, and that keywords are still only recognised exactly.
+#[test]
+fn test_identifier_syntax() {
+    let source = "x_1 _tmp x' x'' σ τ_2' if_ if' iff _";
+    let tokens: Vec<_> = Lexer::new(source.chars())
+        .map(|result| result.unwrap())
+        .filter(|token| !token.inner.is_trivia())
+        .collect();
+
+    let texts: Vec<_> = tokens
+        .iter()
+        .map(|token| &source[token.span.0..token.span.1])
+        .collect();
+
+    assert_eq!(
+        texts,
+        vec!["x_1", "_tmp", "x'", "x''", "σ", "τ_2'", "if_", "if'", "iff", "_"]
+    );
+    assert!(tokens.iter().all(|token| token.inner == Token::Identifier));
+}
+
+/// Test that primes end an identifier, and can't start one.
+#[test]
+fn test_identifier_primes() {
+    let tokens: Vec<_> = Lexer::new("x'y".chars())
+        .map(|result| result.unwrap().inner)
+        .collect();
+
+    assert_eq!(tokens, vec![Token::Identifier, Token::Identifier]);
+    assert!(Lexer::new("'x".chars()).next().unwrap().is_err());
+}
+
+/// Test that the delimiters of the delimited block style lex as tokens, and
+/// that `begin` and `end` are keywords.
+#[test]
+fn test_block_delimiters() {
+    let tokens: Vec<_> = Lexer::new("{ } begin end ending".chars())
+        .map(|result| result.unwrap().inner)
+        .filter(|token| !token.is_trivia())
+        .collect();
+
+    assert_eq!(
+        tokens,
+        vec![
+            Token::LeftBrace,
+            Token::RightBrace,
+            Token::Begin,
+            Token::End,
+            Token::Identifier
+        ]
+    );
+}
+
+/// Check that `assert` and `assume` are keywords.
+#[test]
+fn test_check_keywords() {
+    let tokens: Vec<_> = Lexer::new("assert assume asserted".chars())
+        .map(|result| result.unwrap().inner)
+        .filter(|token| !token.is_trivia())
+        .collect();
+
+    assert_eq!(
+        tokens,
+        vec![Token::Assert, Token::Assume, Token::Identifier]
+    );
+}
+
+/// Check that commas are lexed on their own, without spaces around them.
+#[test]
+fn test_comma() {
+    let tokens: Vec<_> = Lexer::new("f(a,b)".chars())
+        .map(|result| result.unwrap().inner)
+        .collect();
+
+    assert_eq!(
+        tokens,
+        vec![
+            Token::Identifier,
+            Token::LeftParen,
+            Token::Identifier,
+            Token::Comma,
+            Token::Identifier,
+            Token::RightParen
+        ]
+    );
+}
@@ -0,0 +1,110 @@
+use while_tools::ast::Ast;
+use while_tools::lexer::{Lexer, Span, Spanned};
+use while_tools::lint::{self, Lint};
+use while_tools::parser::{BlockStyle, Parser};
+
+/// Check that the non-ASCII operator lint reports every Unicode operator, with
+/// its ASCII form.
+#[test]
+fn test_lint_non_ascii_operators() {
+    let source = "y := x − 1; b := ¬(x ≤ y)";
+    let tokens: Vec<_> = Lexer::new(source.chars()).map(Result::unwrap).collect();
+
+    let lints = lint::non_ascii_operators(source, &tokens);
+
+    assert_eq!(
+        lints,
+        vec![
+            Lint::new("`−` is the non-ASCII form of `-`".to_string(), Span(7, 10)),
+            Lint::new("`¬` is the non-ASCII form of `!`".to_string(), Span(19, 21)),
+            Lint::new(
+                "`≤` is the non-ASCII form of `<=`".to_string(),
+                Span(24, 27)
+            ),
+        ]
+    );
+}
+
+/// Check that the non-ASCII operator lint has nothing to say about ASCII
+/// operators.
+#[test]
+fn test_lint_non_ascii_operators_ascii_source() {
+    let source = "y := x - 1; b := !(x <= y); W := [[skip]]";
+    let tokens: Vec<_> = Lexer::new(source.chars()).map(Result::unwrap).collect();
+
+    assert!(lint::non_ascii_operators(source, &tokens).is_empty());
+}
+
+fn parse(source: &str) -> Spanned<Ast> {
+    let tokens: Vec<_> = Lexer::new(source.chars()).map(Result::unwrap).collect();
+    Parser::new(source, tokens).parse().unwrap()
+}
+
+/// Check that the greedy block lint reports an `if` or `while` whose last
+/// block takes the statements after it, suggesting brackets.
+#[test]
+fn test_lint_greedy_blocks() {
+    let source = "while x <= 3 do x := x + 1; y := x; z := y";
+    let lints = lint::greedy_blocks(&parse(source));
+
+    assert_eq!(lints.len(), 1);
+    assert_eq!(lints[0].span, Span(0, 42));
+    assert_eq!(
+        lints[0].message,
+        "Every statement after `do` is part of this `while`"
+    );
+    assert_eq!(
+        lints[0].note.as_deref(),
+        Some("if only the first of them is meant to be, write `(while x <= 3 do x := x + 1); y := x; z := y`")
+    );
+
+    let source = "if x = 1 then skip else y := 1; z := 2";
+    let lints = lint::greedy_blocks(&parse(source));
+    assert_eq!(
+        lints[0].message,
+        "Every statement after `else` is part of this `if`"
+    );
+
+    // A short `if`'s last block is its `then` block.
+    let source = "if x = 1 then y := 1; z := 2";
+    let lints = lint::greedy_blocks(&parse(source));
+    assert_eq!(
+        lints[0].message,
+        "Every statement after `then` is part of this `if`"
+    );
+}
+
+/// Check that nested greedy blocks are each reported, including those in
+/// definitions, apart from ones in brackets of their own.
+#[test]
+fn test_lint_greedy_blocks_nested() {
+    let source = "W := [[while true do (if true then skip; skip); while true do skip; skip]]";
+    let lints = lint::greedy_blocks(&parse(source));
+
+    let spans: Vec<_> = lints.iter().map(|lint| lint.span).collect();
+    assert_eq!(spans, vec![Span(7, 72), Span(48, 72)]);
+}
+
+/// Check that the greedy block lint has nothing to say about blocks in
+/// brackets, blocks of one statement, or delimited blocks.
+#[test]
+fn test_lint_greedy_blocks_bracketed() {
+    for source in [
+        "(while true do skip); skip",
+        "while true do (skip; skip)",
+        "if true then (skip; skip) else (skip; skip)",
+        "(if true then skip); skip",
+        "while true do skip",
+        "(while x <= 1 do x := 1; y := 2)",
+        "x := 0; (if true then skip else skip; skip); skip",
+    ] {
+        assert!(lint::greedy_blocks(&parse(source)).is_empty(), "{source}");
+    }
+
+    let source = "while true do { skip; skip }; skip";
+    let tokens: Vec<_> = Lexer::new(source.chars()).map(Result::unwrap).collect();
+    let mut parser = Parser::new(source, tokens);
+    parser.set_block_style(BlockStyle::Delimited);
+
+    assert!(lint::greedy_blocks(&parser.parse().unwrap()).is_empty());
+}
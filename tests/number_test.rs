@@ -0,0 +1,112 @@
+use while_tools::number::{Integer, IntegerType, Rounding};
+
+fn int(s: &str) -> Integer {
+    s.parse().unwrap()
+}
+
+/// Check that integers survive a round trip through their string form.
+#[test]
+fn test_integer_display_round_trip() {
+    for s in [
+        "0",
+        "7",
+        "-7",
+        "4294967296",
+        "-18446744073709551617",
+        "1000000000000000000000",
+    ] {
+        assert_eq!(int(s).to_string(), s);
+    }
+
+    assert_eq!(int("-0"), Integer::zero());
+    assert_eq!(int("007").to_string(), "7");
+    assert!("".parse::<Integer>().is_err());
+    assert!("-".parse::<Integer>().is_err());
+    assert!("1a".parse::<Integer>().is_err());
+}
+
+/// Check arithmetic across limb boundaries and mixed signs.
+#[test]
+fn test_integer_arithmetic() {
+    assert_eq!(&int("4294967295") + &int("1"), int("4294967296"));
+    assert_eq!(&int("-5") + &int("3"), int("-2"));
+    assert_eq!(&int("5") + &int("-5"), Integer::zero());
+    assert_eq!(&int("3") - &int("10"), int("-7"));
+    assert_eq!(&int("4294967296") - &int("1"), int("4294967295"));
+    assert_eq!(
+        &int("-123456789012345678901234567890") * &int("987654321"),
+        int("-121932631124828532112482853211126352690")
+    );
+}
+
+/// Check ordering and the conversion to `i64`.
+#[test]
+fn test_integer_ordering_and_to_i64() {
+    assert!(int("-10") < int("-2"));
+    assert!(int("-1") < Integer::zero());
+    assert!(int("4294967296") > int("4294967295"));
+
+    assert_eq!(Integer::from(i64::MIN).to_i64(), Some(i64::MIN));
+    assert_eq!(Integer::from(i64::MAX).to_i64(), Some(i64::MAX));
+    assert_eq!(int("9223372036854775808").to_i64(), None);
+    assert_eq!(int("-9223372036854775809").to_i64(), None);
+}
+
+/// Check the range of each integer type.
+#[test]
+fn test_integer_type_contains() {
+    assert!(IntegerType::I32.contains(&Integer::from(i32::MIN)));
+    assert!(!IntegerType::I32.contains(&int("2147483648")));
+    assert!(IntegerType::I64.contains(&int("2147483648")));
+    assert!(!IntegerType::I64.contains(&int("9223372036854775808")));
+    assert!(IntegerType::Big.contains(&int("9223372036854775808")));
+
+    assert_eq!("big".parse::<IntegerType>(), Ok(IntegerType::Big));
+    assert!("u8".parse::<IntegerType>().is_err());
+}
+
+/// Check that truncated division rounds towards zero, and Euclidean division
+/// keeps the remainder positive, for every combination of signs.
+#[test]
+fn test_integer_div_rem() {
+    let div_rem = |a: i64, b: i64, rounding| {
+        let (q, r) = Integer::from(a)
+            .div_rem(&Integer::from(b), rounding)
+            .unwrap();
+        (q.to_i64().unwrap(), r.to_i64().unwrap())
+    };
+
+    assert_eq!(div_rem(7, 2, Rounding::Truncated), (3, 1));
+    assert_eq!(div_rem(-7, 2, Rounding::Truncated), (-3, -1));
+    assert_eq!(div_rem(7, -2, Rounding::Truncated), (-3, 1));
+    assert_eq!(div_rem(-7, -2, Rounding::Truncated), (3, -1));
+
+    assert_eq!(div_rem(7, 2, Rounding::Euclidean), (3, 1));
+    assert_eq!(div_rem(-7, 2, Rounding::Euclidean), (-4, 1));
+    assert_eq!(div_rem(7, -2, Rounding::Euclidean), (-3, 1));
+    assert_eq!(div_rem(-7, -2, Rounding::Euclidean), (4, 1));
+
+    assert_eq!(div_rem(-6, 3, Rounding::Euclidean), (-2, 0));
+    assert!(Integer::from(1)
+        .div_rem(&Integer::zero(), Rounding::Truncated)
+        .is_none());
+}
+
+/// Check division with divisors of more than one limb.
+#[test]
+fn test_integer_div_rem_big() {
+    let a = int("-121932631124828532112482853211126352690");
+    let b = int("123456789012345678901");
+
+    assert_eq!(
+        a.div_rem(&b, Rounding::Truncated),
+        Some((int("-987654321000000000"), int("-231671990126352690")))
+    );
+    assert_eq!(
+        a.div_rem(&b, Rounding::Euclidean),
+        Some((int("-987654321000000001"), int("123225117022219326211")))
+    );
+
+    assert_eq!("euclidean".parse::<Rounding>(), Ok(Rounding::Euclidean));
+    assert!("floor".parse::<Rounding>().is_err());
+}
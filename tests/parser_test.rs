@@ -1,9 +1,10 @@
 use while_tools::ast::Ast;
-use while_tools::lexer::Lexer;
+use while_tools::lexer::{Lexer, Span, Spanned, Token};
+use while_tools::number::IntegerType;
 use while_tools::parser::*;
 use while_tools::{
-    ass_stmt, binary_node, comp_stmt, ident, if_stmt, less_eq, literal, literal_true, skip_stmt,
-    while_stmt,
+    add, and, ass_stmt, binary_node, comp_stmt, eq, ident, if_stmt, implies, less_eq, literal,
+    literal_true, not, or, skip_stmt, sub, while_stmt,
 };
 
 /// Almost like a procedural macro to make the pipeline of building an AST
@@ -109,3 +110,1041 @@ fn test_parsing_statement_block_paren_respect() {
 
     assert_ast("(while true do skip); skip", &ast);
 }
+
+/// Check that comments are skipped over by the parser, wherever they are.
+#[test]
+fn test_parsing_comments() {
+    let ast = while_stmt!(
+        less_eq!(ident!("x".to_string()), literal!(5)),
+        ass_stmt!("x".to_string(), literal!(1))
+    );
+
+    assert_ast(
+        "// Leading comment\nwhile /* inline */ x <= 5 do // trailing\n x := /* a /* nested */ one */ 1",
+        &ast,
+    );
+}
+
+/// Check that comments and blank lines on lines of their own, between
+/// statements or at the start, don't leave empty statements behind, while
+/// `;;` still does.
+#[test]
+fn test_parsing_full_line_comments() {
+    let ast = comp_stmt!(
+        ass_stmt!("x".to_string(), literal!(1)),
+        ass_stmt!("y".to_string(), literal!(2))
+    );
+
+    for source in [
+        "x := 1\n// note\ny := 2",
+        "x := 1\n/* c */\ny := 2",
+        "x := 1;\n  // indented\n\n  \ny := 2\n// trailing\n",
+        "// header\n/* more */\nx := 1\ny := 2",
+        "x := 1 ; ; y := 2",
+    ] {
+        assert_ast(source, &ast);
+    }
+
+    let error = parse_error("x := 1;;\n// note\ny := 2");
+    assert_eq!(error.message, "Expected a statement, found `;`");
+    assert!(error.note.unwrap().contains("two `;`s in a row"));
+}
+
+/// Check that identifiers and literals are extracted correctly when they come
+/// after multi-byte characters.
+#[test]
+fn test_parsing_after_multibyte_characters() {
+    let ast = comp_stmt!(
+        ass_stmt!(
+            "x".to_string(),
+            binary_node!(Sub, ident!("y"), literal!(10))
+        ),
+        while_stmt!(
+            less_eq!(ident!("x"), ident!("yy")),
+            ass_stmt!("zz".to_string(), literal!(2))
+        )
+    );
+
+    assert_ast(
+        "// σ → 😀\nx := y − 10 /* ≠ */; while x ≤ yy do zz ≜ 2",
+        &ast,
+    );
+}
+
+/// Check that integer literals that don't fit the integer type are rejected,
+/// and that a wider integer type accepts them.
+#[test]
+fn test_parsing_out_of_range_literals() {
+    let source = "x := 99999999999";
+    let tokens: Vec<_> = Lexer::new(source.chars())
+        .collect::<Result<_, _>>()
+        .unwrap();
+
+    let error = Parser::new(source, tokens.iter().copied())
+        .parse()
+        .unwrap_err();
+
+    assert_eq!(error.span, Span(5, 16));
+    assert!(error.message.contains("out of range for i32"));
+
+    for integer_type in [IntegerType::I64, IntegerType::Big] {
+        let mut parser = Parser::new(source, tokens.iter().copied());
+        parser.set_integer_type(integer_type);
+
+        assert_eq!(
+            parser.parse().unwrap(),
+            ass_stmt!("x".to_string(), literal!(99999999999i64))
+        );
+    }
+
+    assert_ast(
+        "x := 2147483647",
+        &ass_stmt!("x".to_string(), literal!(2147483647)),
+    );
+}
+
+/// Check that a negated literal is range-checked after negation, so that the
+/// minimum of the integer type can be written but nothing below it.
+#[test]
+fn test_parsing_negative_literals() {
+    assert_ast(
+        "x := -2147483648",
+        &ass_stmt!("x".to_string(), sub!(literal!(0), literal!(2147483648i64))),
+    );
+
+    let source = "x := -2147483649";
+    let tokens: Vec<_> = Lexer::new(source.chars())
+        .collect::<Result<_, _>>()
+        .unwrap();
+
+    let error = Parser::new(source, tokens.iter().copied())
+        .parse()
+        .unwrap_err();
+
+    assert_eq!(error.span, Span(6, 16));
+    assert_eq!(
+        error.message,
+        "Integer literal `-2147483649` is out of range for i32"
+    );
+}
+
+/// Check that the keywords added for annotations, checks and blocks are
+/// reserved, and can't be assigned to like variables.
+#[test]
+fn test_parsing_reserved_words() {
+    let error = parse_error("var := 1");
+    assert_eq!(error.message, "Expected a statement, found `var`");
+    assert_eq!(error.span, Span(0, 3));
+
+    for word in ["inv", "var", "assert", "assume", "begin", "end"] {
+        let source = format!("x := 1; {word} := x");
+        let (_, errors) = parser(&source).parse_recovering();
+        assert_eq!(errors[0].kind, ParseErrorKind::UnexpectedToken, "{source}");
+    }
+}
+
+/// Parses a program, panicking on any error.
+fn parse(source: &str) -> Spanned<Ast> {
+    let tokens: Vec<_> = Lexer::new(source.chars())
+        .collect::<Result<_, _>>()
+        .unwrap();
+
+    Parser::new(source, tokens).parse().unwrap()
+}
+
+/// Check that every node has the span of the source it was parsed from.
+#[test]
+fn test_parsing_spans() {
+    let source = "x := 1 + y * 2; while x do (skip)";
+    let ast = parse(source);
+    let text = |span: Span| &source[span.0..span.1];
+
+    assert_eq!(ast.span, Span(0, 33));
+
+    let Ast::Comp { first, second } = &ast.inner else {
+        panic!("expected a composition, found {ast:?}");
+    };
+    assert_eq!(text(first.span), "x := 1 + y * 2");
+    assert_eq!(text(second.span), "while x do (skip)");
+
+    let Ast::Ass { value, .. } = &first.inner else {
+        panic!("expected an assignment, found {first:?}");
+    };
+    let Ast::Add { left, right } = &value.inner else {
+        panic!("expected an addition, found {value:?}");
+    };
+    assert_eq!(text(value.span), "1 + y * 2");
+    assert_eq!(text(left.span), "1");
+    assert_eq!(text(right.span), "y * 2");
+
+    // A parenthesised block's span includes its parentheses.
+    let Ast::While { cond, body, .. } = &second.inner else {
+        panic!("expected a while loop, found {second:?}");
+    };
+    assert_eq!(text(cond.span), "x");
+    assert_eq!(text(body.span), "(skip)");
+}
+
+/// Check that lowered forms have the span of the source they were lowered
+/// from.
+#[test]
+fn test_parsing_lowered_spans() {
+    let source = "b := a < -c";
+    let ast = parse(source);
+
+    let Ast::Ass { value, .. } = &ast.inner else {
+        panic!("expected an assignment, found {ast:?}");
+    };
+    let Ast::Not { expr, .. } = &value.inner else {
+        panic!("expected a negation, found {value:?}");
+    };
+    let Ast::LessEq { left, right } = &expr.inner else {
+        panic!("expected a comparison, found {expr:?}");
+    };
+    let Ast::Sub { left: zero, .. } = &left.inner else {
+        panic!("expected a subtraction, found {left:?}");
+    };
+
+    assert_eq!(value.span, Span(5, 11));
+    assert_eq!(expr.span, Span(5, 11));
+    assert_eq!(left.span, Span(9, 11));
+    assert_eq!(zero.span, Span(9, 10));
+    assert_eq!(right.span, Span(5, 6));
+
+    // The implicit `else skip` is empty, at the end of the `if`.
+    let ast = parse("if true then skip");
+    let Ast::If { false_path, .. } = &ast.inner else {
+        panic!("expected an if statement, found {ast:?}");
+    };
+    assert_eq!(false_path.span, Span(17, 17));
+}
+
+/// Check that comparisons ignore spans, so that parsed trees can be compared
+/// with ones built by hand.
+#[test]
+fn test_comparisons_ignore_spans() {
+    assert_eq!(parse("x := 1"), parse("  x   :=   1  "));
+    assert_ne!(parse("x := 1"), parse("x := 2"));
+}
+
+/// Parses a program with error recovery, returning the tree along with the
+/// text of each error's span.
+fn parse_recovering(source: &str) -> (Spanned<Ast>, Vec<&str>) {
+    let tokens: Vec<_> = Lexer::new(source.chars())
+        .collect::<Result<_, _>>()
+        .unwrap();
+
+    let (ast, errors) = Parser::new(source, tokens).parse_recovering();
+    let texts = errors.iter().map(|e| &source[e.span.0..e.span.1]).collect();
+
+    (ast, texts)
+}
+
+/// Check that recovery finds every error, and keeps the statements around
+/// them.
+#[test]
+fn test_parse_recovering() {
+    let (ast, errors) = parse_recovering("x := 1 +; y := 2; z := then; w := 3");
+
+    assert_eq!(errors, vec![";", "then"]);
+    assert_eq!(
+        ast,
+        comp_stmt!(
+            comp_stmt!(
+                comp_stmt!(Ast::Error, ass_stmt!("y".to_string(), literal!(2))),
+                Ast::Error
+            ),
+            ass_stmt!("w".to_string(), literal!(3))
+        )
+    );
+}
+
+/// Check that recovery stops at `else`, `)` and `]]`, so that the statement
+/// around the error still parses.
+#[test]
+fn test_parse_recovering_sync_points() {
+    let (ast, errors) = parse_recovering("if true then x := * else y := 1");
+    assert_eq!(errors, vec!["*"]);
+    assert_eq!(
+        ast,
+        if_stmt!(
+            literal_true!(),
+            Ast::Error,
+            ass_stmt!("y".to_string(), literal!(1))
+        )
+    );
+
+    // The missing `]]` fails the whole definition, but `)` still closes the
+    // first statement.
+    let (ast, errors) = parse_recovering("(skip; x := := 1); W := [[y := )]]; W");
+    assert_eq!(errors, vec![":=", ")"]);
+    assert_eq!(
+        ast,
+        comp_stmt!(
+            comp_stmt!(comp_stmt!(skip_stmt!(), Ast::Error), Ast::Error),
+            Ast::DefinitionRun {
+                ident: "W".to_string()
+            }
+        )
+    );
+}
+
+/// Check that brackets opened within a failed statement are skipped over
+/// along with it.
+#[test]
+fn test_parse_recovering_nested_brackets() {
+    let (ast, errors) = parse_recovering("x := (1 + * 2); y := 2");
+
+    assert_eq!(errors, vec!["*"]);
+    assert_eq!(
+        ast,
+        comp_stmt!(Ast::Error, ass_stmt!("y".to_string(), literal!(2)))
+    );
+}
+
+/// Check that stray closing tokens are reported, and parsing carries on after
+/// them.
+#[test]
+fn test_parse_recovering_stray_tokens() {
+    let (ast, errors) = parse_recovering("x := 1) y := 2; else");
+
+    assert_eq!(errors, vec![")", "else"]);
+    assert_eq!(
+        ast,
+        comp_stmt!(
+            comp_stmt!(
+                comp_stmt!(ass_stmt!("x".to_string(), literal!(1)), Ast::Error),
+                ass_stmt!("y".to_string(), literal!(2))
+            ),
+            Ast::Error
+        )
+    );
+}
+
+/// Check that valid programs parse the same with and without recovery.
+#[test]
+fn test_parse_recovering_valid_program() {
+    let source = "x := 1; while x < 10 do (x := x * 2; skip)";
+    let (ast, errors) = parse_recovering(source);
+
+    assert!(errors.is_empty());
+    assert_eq!(ast, parse(source));
+}
+
+/// Parses the source, returning the first error.
+fn parse_error(source: &str) -> ParseError {
+    let tokens: Vec<_> = Lexer::new(source.chars())
+        .collect::<Result<_, _>>()
+        .unwrap();
+
+    Parser::new(source, tokens).parse().unwrap_err()
+}
+
+/// Check that errors record what was expected and found, and are written
+/// with the tokens' spellings.
+#[test]
+fn test_parse_error_is_structured() {
+    let error = parse_error("x := (1 + 2; skip");
+
+    assert_eq!(error.kind, ParseErrorKind::UnexpectedToken);
+    assert_eq!(error.expected, vec![Token::RightParen]);
+    assert_eq!(error.found, Some(Token::Semicolon));
+    assert_eq!(error.span, Span(11, 12));
+    assert_eq!(error.message, "Expected `)`, found `;`");
+    assert_eq!(error.note, None);
+
+    let error = parse_error("while x ≤ 1");
+
+    assert_eq!(error.kind, ParseErrorKind::UnexpectedEnd);
+    assert_eq!(error.found, None);
+    assert_eq!(
+        error.message,
+        "Expected `inv`, `var` or `do`, found end of input"
+    );
+
+    let error = parse_error("x := 1 + then");
+    assert_eq!(error.message, "Expected an expression, found `then`");
+
+    let error = parse_error("else skip");
+    assert_eq!(error.message, "Expected a statement, found `else`");
+
+    let error = parse_error("x := 99999999999");
+    assert_eq!(error.kind, ParseErrorKind::LiteralOutOfRange);
+}
+
+/// Check that common mistakes get a note on their likely cause.
+#[test]
+fn test_parse_error_notes() {
+    let error = parse_error("if x = 1 then y := 1;; else y := 2");
+    assert_eq!(error.found, Some(Token::Semicolon));
+    assert!(error.note.unwrap().contains("two `;`s in a row"));
+
+    let error = parse_error("x = 1");
+    assert!(error.note.unwrap().contains("use `:=` to assign"));
+
+    let error = parse_error("while x := 1 do skip");
+    assert!(error.note.unwrap().contains("use `=` to compare"));
+
+    let source = "while x = 1 do y := 1; else y := 2";
+    let tokens: Vec<_> = Lexer::new(source.chars())
+        .collect::<Result<_, _>>()
+        .unwrap();
+    let (_, errors) = Parser::new(source, tokens).parse_recovering();
+
+    assert_eq!(errors[0].message, "Unexpected `else`");
+    assert!(errors[0]
+        .note
+        .as_ref()
+        .unwrap()
+        .contains("the `;` before this `else`"));
+}
+
+/// Makes a parser for the source, which must lex.
+fn parser(source: &str) -> Parser<'_, std::vec::IntoIter<Spanned<Token>>> {
+    let tokens: Vec<_> = Lexer::new(source.chars())
+        .collect::<Result<_, _>>()
+        .unwrap();
+
+    Parser::new(source, tokens)
+}
+
+/// Check that arithmetic and boolean expressions can be parsed on their own.
+#[test]
+fn test_parse_aexp_and_bexp() {
+    assert_eq!(
+        parser("x * (y + 1)").parse_aexp().unwrap(),
+        binary_node!(
+            Mul,
+            ident!("x"),
+            binary_node!(Add, ident!("y"), literal!(1))
+        )
+    );
+    assert_eq!(
+        parser("x <= 1 & true").parse_bexp().unwrap(),
+        binary_node!(And, less_eq!(ident!("x"), literal!(1)), literal_true!())
+    );
+    assert_eq!(parser("1 = 2").parse_bexp().unwrap().span, Span(0, 5));
+}
+
+/// Check that standalone parses must consume the whole input.
+#[test]
+fn test_standalone_parses_consume_everything() {
+    let error = parser("x + 1 )").parse_aexp().unwrap_err();
+    assert_eq!(error.message, "Unexpected `)`");
+    assert_eq!(error.span, Span(6, 7));
+
+    let error = parser("x := 1) skip").parse_stmt().unwrap_err();
+    assert_eq!(error.span, Span(6, 7));
+
+    let error = parser("x := 1; y := 2 skip").parse_stmt().unwrap_err();
+    assert_eq!(error.message, "Unexpected `skip`");
+}
+
+/// Check that standalone parses reject the wrong syntactic category, pointing
+/// at the part that's wrong.
+#[test]
+fn test_standalone_parses_check_category() {
+    let error = parser("x <= 1").parse_aexp().unwrap_err();
+    assert_eq!(error.kind, ParseErrorKind::WrongCategory);
+    assert_eq!(
+        error.message,
+        "Expected an arithmetic expression, found a boolean expression"
+    );
+
+    let error = parser("x + 1").parse_bexp().unwrap_err();
+    assert_eq!(
+        error.message,
+        "Expected a boolean expression, found an arithmetic expression"
+    );
+
+    let error = parser("x + true").parse_aexp().unwrap_err();
+    assert_eq!(error.span, Span(4, 8));
+
+    let error = parser("skip; x := 1 = 1").parse_stmt().unwrap_err();
+    assert_eq!(error.span, Span(11, 16));
+
+    let error = parser("while x + 1 do skip").parse_stmt().unwrap_err();
+    assert_eq!(error.span, Span(6, 11));
+
+    let error = parser("if true = 1 then skip").parse_stmt().unwrap_err();
+    assert_eq!(error.span, Span(10, 11));
+
+    // Booleans can be compared with each other, and definitions hold
+    // statements.
+    assert!(parser("!(x = 1) = false").parse_bexp().is_ok());
+    assert!(parser("W := [[x := 1]]; W").parse_stmt().is_ok());
+}
+
+/// Check that `|` and `=>` lower to `!` and `&`, with `&` binding tighter than
+/// `|`, which binds tighter than `=>`.
+#[test]
+fn test_parsing_disjunction_and_implication() {
+    let (a, b, c) = (literal_true!(), eq!(ident!("x"), literal!(1)), ident!("y"));
+    let c = eq!(c, literal!(2));
+
+    assert_eq!(
+        parser("true | x = 1 & y = 2").parse_bexp().unwrap(),
+        or!(a.clone(), and!(b.clone(), c.clone()))
+    );
+    assert_eq!(
+        parser("true ∨ x = 1 ⇒ y = 2").parse_bexp().unwrap(),
+        implies!(or!(a.clone(), b.clone()), c.clone())
+    );
+    assert_eq!(
+        parser("true | x = 1 | y = 2").parse_bexp().unwrap(),
+        or!(or!(a.clone(), b.clone()), c.clone())
+    );
+
+    // Implication is right associative.
+    assert_eq!(
+        parser("true => x = 1 => y = 2").parse_bexp().unwrap(),
+        implies!(a.clone(), implies!(b.clone(), c.clone()))
+    );
+
+    // The sugar is recorded, so it isn't the same as writing it out by hand.
+    assert_ne!(
+        parser("true | x = 1").parse_bexp().unwrap(),
+        not!(and!(not!(a), not!(b)))
+    );
+}
+
+/// Check that the lowered nodes of `|` and `=>` have the span of the whole
+/// operation.
+#[test]
+fn test_parsing_disjunction_spans() {
+    let expr = parser("x = 1 | true").parse_bexp().unwrap();
+    assert_eq!(expr.span, Span(0, 12));
+
+    let Ast::Not { expr: and, .. } = &expr.inner else {
+        panic!("expected a negation, found {expr:?}");
+    };
+    let Ast::And { left, .. } = &and.inner else {
+        panic!("expected a conjunction, found {and:?}");
+    };
+    let Ast::Not { expr: operand, .. } = &left.inner else {
+        panic!("expected a negation, found {left:?}");
+    };
+
+    assert_eq!((and.span, left.span), (Span(0, 12), Span(0, 12)));
+    assert_eq!(operand.span, Span(0, 5));
+}
+
+/// Check that trees are printed back out as source that parses to the same
+/// tree, with `|` and `=>` written as they were.
+#[test]
+fn test_printing_round_trips() {
+    let statements = [
+        "x := 1; y := x * (2 + 3) - (4 - 5)",
+        "if x = 1 | true & false => y <= 2 => !true then skip else x := 0 - x",
+        "(while x <= 1 do x := x + 1); y := 2",
+        "W := [[x := 1; (if true then skip else skip); y := 2]]; W",
+    ];
+
+    for source in statements {
+        let printed = parse(source).inner.to_string();
+
+        assert_eq!(printed, source);
+        assert_eq!(parse(&printed), parse(source));
+    }
+
+    for source in [
+        "(true => false) => !(x = 1 | y = 2) & (true | false)",
+        "!(x <= 1) & !(1 + 2 = 3)",
+    ] {
+        let printed = parser(source).parse_bexp().unwrap().inner.to_string();
+        assert_eq!(printed, source);
+    }
+
+    // Comparisons other than `<=` and `=` are written in their lowered forms.
+    assert_eq!(
+        parse("x := 1; if x < 2 then skip").inner.to_string(),
+        "x := 1; if !(2 <= x) then skip else skip"
+    );
+}
+
+/// Check that `/` and `%` bind as tightly as `*`, and associate to the left.
+#[test]
+fn test_parsing_division() {
+    assert_eq!(
+        parser("a + b / c % d * e").parse_aexp().unwrap(),
+        binary_node!(
+            Add,
+            ident!("a"),
+            binary_node!(
+                Mul,
+                binary_node!(
+                    Mod,
+                    binary_node!(Div, ident!("b"), ident!("c")),
+                    ident!("d")
+                ),
+                ident!("e")
+            )
+        )
+    );
+
+    let printed = parser("a / (b % c) - d")
+        .parse_aexp()
+        .unwrap()
+        .inner
+        .to_string();
+    assert_eq!(printed, "a / (b % c) - d");
+}
+
+/// Parses the source in the delimited block style.
+fn parse_delimited(source: &str) -> Result<Spanned<Ast>, ParseError> {
+    let mut parser = parser(source);
+    parser.set_block_style(BlockStyle::Delimited);
+    parser.parse()
+}
+
+/// Check that in the delimited block style, bodies end at their closing
+/// delimiter, rather than taking every statement after them.
+#[test]
+fn test_parsing_delimited_blocks() {
+    let loop_then_skip = comp_stmt!(
+        while_stmt!(literal_true!(), comp_stmt!(skip_stmt!(), skip_stmt!())),
+        skip_stmt!()
+    );
+
+    assert_eq!(
+        parse_delimited("while true do { skip; skip }; skip").unwrap(),
+        loop_then_skip
+    );
+    assert_eq!(
+        parse_delimited("while true do begin skip; skip end; skip").unwrap(),
+        loop_then_skip
+    );
+
+    // The same source in the greedy style puts the last `skip` in the loop.
+    assert_eq!(
+        parse("while true do (skip; skip); skip"),
+        while_stmt!(
+            literal_true!(),
+            comp_stmt!(comp_stmt!(skip_stmt!(), skip_stmt!()), skip_stmt!())
+        )
+    );
+
+    assert_eq!(
+        parse_delimited("if true then { x := 1 } else begin W end; y := 2").unwrap(),
+        comp_stmt!(
+            if_stmt!(
+                literal_true!(),
+                ass_stmt!("x".to_string(), literal!(1)),
+                Ast::DefinitionRun {
+                    ident: "W".to_string()
+                }
+            ),
+            ass_stmt!("y".to_string(), literal!(2))
+        )
+    );
+
+    // Blocks can also be used as statements of their own, and span their
+    // delimiters.
+    let ast = parse_delimited("{ x := 1 }; begin\n  skip\nend").unwrap();
+    let Ast::Comp { first, second } = &ast.inner else {
+        panic!("expected a composition, got {ast:?}");
+    };
+    assert_eq!((first.span, second.span), (Span(0, 10), Span(12, 28)));
+}
+
+/// Check that each block style rejects the other's bodies, with a note on why.
+#[test]
+fn test_block_style_errors() {
+    let error = parse_delimited("while true do skip").unwrap_err();
+    assert_eq!(error.expected, vec![Token::LeftBrace, Token::Begin]);
+    assert_eq!(error.message, "Expected `{` or `begin`, found `skip`");
+    assert!(error
+        .note
+        .unwrap()
+        .contains("go in `{ }` or `begin ... end`"));
+
+    let error = parse_delimited("if true then { skip } else begin skip }").unwrap_err();
+    assert_eq!(error.message, "Expected `end`, found `}`");
+
+    let error = parse_error("while true do { skip }");
+    assert_eq!(error.message, "Expected a statement, found `{`");
+    assert!(error
+        .note
+        .unwrap()
+        .contains("only allowed in the delimited"));
+
+    let error = parse_error("while true do begin skip end");
+    assert_eq!(error.message, "Expected `var`, found `skip`");
+    assert!(error
+        .note
+        .unwrap()
+        .contains("without `var` declarations are only allowed in the delimited"));
+
+    // `begin` and `end` are keywords in either style.
+    let error = parse_error("end := 1");
+    assert_eq!(error.message, "Expected a statement, found `end`");
+}
+
+/// Check that errors inside delimited blocks are recovered from at the
+/// closing delimiter.
+#[test]
+fn test_parse_recovering_delimited_blocks() {
+    let source = "while true do { x := ; y := 1 }; z := (";
+    let mut parser = parser(source);
+    parser.set_block_style(BlockStyle::Delimited);
+    let (ast, errors) = parser.parse_recovering();
+
+    assert_eq!(errors.len(), 2, "{errors:?}");
+    let Ast::Comp { first, .. } = &ast.inner else {
+        panic!("expected a composition, got {ast:?}");
+    };
+    assert!(matches!(first.inner, Ast::While { .. }));
+}
+
+/// Check that `{P} S {Q}` parses as a Hoare triple, and that assertions on
+/// their own compose with the statements around them.
+#[test]
+fn test_parsing_triples_and_assertions() {
+    let assertion = |formula: Ast| Ast::Assertion {
+        formula: Box::new(formula.into()),
+    };
+
+    assert_eq!(
+        parse("{x = x0} x := x + 1 {x = x0 + 1}"),
+        Ast::Triple {
+            pre: Box::new(eq!(ident!("x"), ident!("x0")).into()),
+            stmt: Box::new(ass_stmt!("x".to_string(), add!(ident!("x"), literal!(1))).into()),
+            post: Box::new(eq!(ident!("x"), add!(ident!("x0"), literal!(1))).into()),
+        }
+    );
+
+    // A proof outline, where each line is its own statement.
+    assert_eq!(
+        parse("{true}\nx := 1;\n{x = 1}\n{x <= 1}"),
+        comp_stmt!(
+            comp_stmt!(
+                comp_stmt!(
+                    assertion(literal_true!()),
+                    ass_stmt!("x".to_string(), literal!(1))
+                ),
+                assertion(eq!(ident!("x"), literal!(1)))
+            ),
+            assertion(less_eq!(ident!("x"), literal!(1)))
+        )
+    );
+
+    // Without a `;`, an assertion still goes with the statement next to it.
+    assert_eq!(
+        parse("{true} skip; W {false}"),
+        comp_stmt!(
+            comp_stmt!(assertion(literal_true!()), skip_stmt!()),
+            comp_stmt!(
+                Ast::DefinitionRun {
+                    ident: "W".to_string()
+                },
+                assertion(Ast::False)
+            )
+        )
+    );
+}
+
+/// Check the spans of triples and assertions, which include their braces.
+#[test]
+fn test_parsing_triple_spans() {
+    let ast = parse("{x = 1} (while x <= 2 do x := x + 1) {x = 3}");
+    assert_eq!(ast.span, Span(0, 44));
+
+    let Ast::Triple { pre, stmt, post } = &ast.inner else {
+        panic!("expected a triple, got {ast:?}");
+    };
+    assert_eq!(
+        (pre.span, stmt.span, post.span),
+        (Span(1, 6), Span(8, 36), Span(38, 43))
+    );
+
+    // In the greedy style, a while's body takes the assertion after it.
+    let ast = parse("{x = 1} while x <= 2 do x := x + 1 {x = 3}");
+    assert!(matches!(ast.inner, Ast::Comp { .. }), "{ast:?}");
+
+    let ast = parse_delimited("{x = 1} while x <= 2 do { x := x + 1 } {x = 3}").unwrap();
+    assert!(matches!(ast.inner, Ast::Triple { .. }), "{ast:?}");
+}
+
+/// Check that, in the delimited style, `{ }` holds a block of statements or
+/// an assertion depending on what it starts with.
+#[test]
+fn test_parsing_delimited_braces() {
+    let assertion = |formula: Ast| Ast::Assertion {
+        formula: Box::new(formula.into()),
+    };
+
+    assert_eq!(
+        parse_delimited("{x = 1} { x := x + 1 } {x = 2}").unwrap(),
+        Ast::Triple {
+            pre: Box::new(eq!(ident!("x"), literal!(1)).into()),
+            stmt: Box::new(ass_stmt!("x".to_string(), add!(ident!("x"), literal!(1))).into()),
+            post: Box::new(eq!(ident!("x"), literal!(2)).into()),
+        }
+    );
+
+    for source in [
+        "{ W }",
+        "{ W(1) }",
+        "{\n  W\n}",
+        "{ skip; W }",
+        "{ {true} W }",
+    ] {
+        let ast = parse_delimited(source).unwrap();
+        assert!(!matches!(ast.inner, Ast::Assertion { .. }), "{source}");
+    }
+
+    assert_eq!(
+        parse_delimited("{ (x <= 1) & true }").unwrap(),
+        assertion(and!(less_eq!(ident!("x"), literal!(1)), literal_true!()))
+    );
+}
+
+/// Check that a block of statements in `{ }` is pointed out in the greedy
+/// style, and that a block starting with `(` is taken for an assertion in the
+/// delimited style.
+#[test]
+fn test_parsing_assertion_errors() {
+    let error = parse_error("while x <= 1 do { x := x + 1 }");
+    assert_eq!(error.message, "Expected a statement, found `{`");
+    assert!(error
+        .note
+        .unwrap()
+        .contains("use `( )` to group statements"));
+
+    // In the delimited style, a `{` followed by `(` starts an assertion.
+    let error = parse_delimited("{ (x := 1) }").unwrap_err();
+    assert_eq!(error.message, "Expected `)`, found `:=`");
+
+    let error = parser("{x = 1} x := true {true}").parse_stmt().unwrap_err();
+    assert_eq!(error.kind, ParseErrorKind::WrongCategory);
+
+    let error = parser("{x + 1} skip {true}").parse_stmt().unwrap_err();
+    assert_eq!(
+        error.message,
+        "Expected a boolean expression, found an arithmetic expression"
+    );
+}
+
+/// Check that loops can have an invariant and a variant, in that order, and
+/// that each is optional.
+#[test]
+fn test_parsing_loop_annotations() {
+    let ast = parse("while 1 <= x inv 0 <= x & s = x0 var x do x := x - 1");
+    let Ast::While {
+        invariant: Some(invariant),
+        variant: Some(variant),
+        ..
+    } = &ast.inner
+    else {
+        panic!("expected an annotated loop, got {ast:?}");
+    };
+    assert_eq!(
+        invariant.inner,
+        and!(
+            less_eq!(literal!(0), ident!("x")),
+            eq!(ident!("s"), ident!("x0"))
+        )
+    );
+    assert_eq!(variant.inner, ident!("x"));
+    assert_eq!(invariant.span, Span(17, 32));
+    assert_eq!(variant.span, Span(37, 38));
+
+    let ast = parse("while true var 10 - x do skip");
+    assert!(matches!(
+        ast.inner,
+        Ast::While {
+            invariant: None,
+            variant: Some(_),
+            ..
+        }
+    ));
+
+    let ast = parse("while true inv true do skip");
+    assert!(matches!(
+        ast.inner,
+        Ast::While {
+            invariant: Some(_),
+            variant: None,
+            ..
+        }
+    ));
+
+    let printed = parse("while x <= 1 inv x <= 2 var 2 - x do x := x + 1")
+        .inner
+        .to_string();
+    assert_eq!(printed, "while x <= 1 inv x <= 2 var 2 - x do x := x + 1");
+}
+
+/// Check that a loop's annotations must come before `do`, in order, and be
+/// of the right category.
+#[test]
+fn test_parsing_loop_annotation_errors() {
+    let error = parse_error("while true inv true skip");
+    assert_eq!(error.expected, vec![Token::Var, Token::Do]);
+
+    let error = parse_error("while true var x inv true do skip");
+    assert_eq!(error.message, "Expected `do`, found `inv`");
+
+    let error = parser("while true inv x var x do skip")
+        .parse_stmt()
+        .unwrap_err();
+    assert_eq!(error.span, Span(15, 16));
+    assert_eq!(
+        error.message,
+        "Expected a boolean expression, found an arithmetic expression"
+    );
+
+    let error = parser("while true var true do skip")
+        .parse_stmt()
+        .unwrap_err();
+    assert_eq!(error.kind, ParseErrorKind::WrongCategory);
+}
+
+/// Check that `assert` and `assume` take a condition, and are statements of
+/// their own.
+#[test]
+fn test_parsing_assert_and_assume() {
+    let ast = parse("assert x <= 1; assume !(y = 2)");
+    let Ast::Comp { first, second } = &ast.inner else {
+        panic!("expected a composition, got {ast:?}");
+    };
+
+    assert_eq!(
+        first.inner,
+        Ast::Assert {
+            cond: Box::new(less_eq!(ident!("x"), literal!(1)).into())
+        }
+    );
+    assert!(matches!(second.inner, Ast::Assume { .. }));
+    assert_eq!((first.span, second.span), (Span(0, 13), Span(15, 30)));
+
+    assert_eq!(ast.inner.to_string(), "assert x <= 1; assume !(y = 2)");
+
+    let error = parser("assert x + 1").parse_stmt().unwrap_err();
+    assert_eq!(error.kind, ParseErrorKind::WrongCategory);
+
+    let error = parse_error("x := assert");
+    assert_eq!(error.found, Some(Token::Assert));
+}
+
+/// Check that definitions can have inputs and outputs, and that calls give
+/// them expressions and variables respectively.
+#[test]
+fn test_parsing_procedures_and_calls() {
+    let ast = parse("Add(a, b; r) := [[r := a + b]]; Add(x, 3 * y; z)");
+    let Ast::Comp { first, second } = &ast.inner else {
+        panic!("expected a composition, got {ast:?}");
+    };
+
+    assert_eq!(
+        first.inner,
+        Ast::Procedure {
+            ident: "Add".to_string(),
+            inputs: vec!["a".to_string(), "b".to_string()],
+            outputs: vec!["r".to_string()],
+            body: Box::new(ass_stmt!("r".to_string(), add!(ident!("a"), ident!("b"))).into()),
+        }
+    );
+    let Ast::Call {
+        ident,
+        inputs,
+        outputs,
+    } = &second.inner
+    else {
+        panic!("expected a call, got {second:?}");
+    };
+    assert_eq!(ident, "Add");
+    assert_eq!(inputs[0].inner, ident!("x"));
+    assert_eq!(inputs[1].span, Span(39, 44));
+    assert_eq!(outputs, &["z".to_string()]);
+    assert_eq!(second.span, Span(32, 48));
+
+    for source in ["P() := [[skip]]; P()", "Get(; r, s); Put(1, 2)", "F(x)"] {
+        assert_eq!(parse(source).inner.to_string(), source);
+    }
+
+    // A call can end a loop's body, or stand before an assertion.
+    let ast = parse("{true} F(x) {true}; while true do F(x)");
+    assert!(matches!(ast.inner, Ast::Comp { .. }));
+}
+
+/// Check that parameters must be distinct names, and outputs must be
+/// variables.
+#[test]
+fn test_parsing_procedure_errors() {
+    let error = parse_error("Add(a, 1; r) := [[skip]]");
+    assert_eq!(error.kind, ParseErrorKind::BadParameter);
+    assert_eq!(error.span, Span(7, 8));
+
+    let error = parse_error("Add(a, b; a) := [[skip]]");
+    assert_eq!(error.message, "Parameter `a` is named more than once");
+    assert_eq!(error.span, Span(0, 12));
+
+    let error = parse_error("Add(x; z + 1)");
+    assert_eq!(error.expected, vec![Token::Comma, Token::RightParen]);
+
+    let error = parse_error("Add(x y)");
+    assert_eq!(
+        error.message,
+        "Expected `,`, `;` or `)`, found an identifier"
+    );
+
+    let error = parse_error("Add(a; r) := r + 1");
+    assert_eq!(error.expected, vec![Token::LeftSemantic]);
+
+    let error = parser("Add(true; z)").parse_stmt().unwrap_err();
+    assert_eq!(error.kind, ParseErrorKind::WrongCategory);
+}
+
+/// Check that `begin ... end` blocks can declare local variables, in either
+/// block style.
+#[test]
+fn test_parsing_local_blocks() {
+    let source = "begin var x := 1; var y := x + 1; while x <= y do x := x + 1 end; z := x";
+    let ast = parse(source);
+    let Ast::Comp { first, second } = &ast.inner else {
+        panic!("expected a composition, got {ast:?}");
+    };
+
+    let Ast::Block { declarations, body } = &first.inner else {
+        panic!("expected a block, got {first:?}");
+    };
+    assert_eq!(declarations[0], ("x".to_string(), literal!(1).into()));
+    assert_eq!(
+        declarations[1],
+        ("y".to_string(), add!(ident!("x"), literal!(1)).into())
+    );
+    assert!(matches!(body.inner, Ast::While { .. }));
+    assert_eq!(first.span, Span(0, 64));
+    assert_eq!(second.inner, ass_stmt!("z".to_string(), ident!("x")));
+
+    assert_eq!(ast.inner.to_string(), source);
+    assert_eq!(
+        parse_delimited("while true do begin var x := 1; skip end").unwrap(),
+        while_stmt!(
+            literal_true!(),
+            Ast::Block {
+                declarations: vec![("x".to_string(), literal!(1).into())],
+                body: Box::new(skip_stmt!().into()),
+            }
+        )
+    );
+}
+
+/// Check that declarations need a name and a value, and end with a `;`.
+#[test]
+fn test_parsing_local_block_errors() {
+    let error = parse_error("begin var x := 1 end");
+    assert_eq!(error.message, "Expected `;`, found `end`");
+
+    let error = parse_error("begin var 1 := x; skip end");
+    assert_eq!(error.expected, vec![Token::Identifier]);
+
+    let error = parse_error("begin var x := 1; skip");
+    assert_eq!(error.kind, ParseErrorKind::UnexpectedEnd);
+
+    let error = parser("begin var x := true; skip end")
+        .parse_stmt()
+        .unwrap_err();
+    assert_eq!(error.kind, ParseErrorKind::WrongCategory);
+}
@@ -0,0 +1,160 @@
+use while_tools::parser::BlockStyle;
+use while_tools::repl::{Repl, Response};
+
+fn output(text: &str) -> Response {
+    Response::Output(text.to_string())
+}
+
+/// Check that variables persist between inputs.
+#[test]
+fn test_repl_keeps_state() {
+    let mut repl = Repl::new();
+
+    assert_eq!(repl.handle("x := 3"), output("[x -> 3]"));
+    assert_eq!(repl.handle("y := x + 1"), output("[x -> 3, y -> 4]"));
+    assert_eq!(repl.handle(":state"), output("[x -> 3, y -> 4]"));
+}
+
+/// Check that definitions persist between inputs, and can be run by name.
+#[test]
+fn test_repl_keeps_definitions() {
+    let mut repl = Repl::new();
+
+    repl.handle("W := [[x := x + 1]]");
+    repl.handle("V := [[W; W]]");
+
+    assert_eq!(repl.handle(":defs"), output("V\nW"));
+    assert_eq!(repl.handle("V"), output("[x -> 2]"));
+}
+
+/// Check that bare arithmetic and boolean expressions are evaluated.
+#[test]
+fn test_repl_evaluates_expressions() {
+    let mut repl = Repl::new();
+
+    repl.handle("x := 3");
+
+    assert_eq!(repl.handle("x * 2 + 1"), output("7"));
+    assert_eq!(repl.handle("x"), output("3"));
+    assert_eq!(repl.handle("x <= 4 & !false"), output("true"));
+    assert_eq!(repl.handle("x > 4"), output("false"));
+}
+
+/// Check that `:reset` clears both variables and definitions.
+#[test]
+fn test_repl_reset() {
+    let mut repl = Repl::new();
+
+    repl.handle("x := 3; W := [[skip]]");
+    repl.handle(":reset");
+
+    assert_eq!(repl.handle(":state"), output("[]"));
+    assert_eq!(repl.handle(":defs"), output("No definitions."));
+}
+
+/// Check that `:load` runs a file in the current session.
+#[test]
+fn test_repl_load() {
+    let mut repl = Repl::new();
+
+    assert_eq!(
+        repl.handle(":load examples/gcd.while"),
+        output("[x -> 1, y -> 1, z -> 1]")
+    );
+    assert!(matches!(
+        repl.handle(":load examples/does_not_exist.while"),
+        Response::Error(_)
+    ));
+}
+
+/// Check that parse errors are annotated with the offending source.
+#[test]
+fn test_repl_parse_error_annotated() {
+    let mut repl = Repl::new();
+
+    let Response::Error(message) = repl.handle("if x then") else {
+        panic!("Expected an error");
+    };
+
+    assert!(message.contains("1 | if x then"));
+
+    // The session should carry on as normal afterwards.
+    assert_eq!(repl.handle("x := 1"), output("[x -> 1]"));
+}
+
+/// Check the miscellaneous commands.
+#[test]
+fn test_repl_commands() {
+    let mut repl = Repl::new();
+
+    assert_eq!(repl.handle(":quit"), Response::Quit);
+    assert_eq!(repl.handle("   "), output(""));
+    assert!(matches!(repl.handle(":frobnicate"), Response::Error(_)));
+}
+
+/// Check that a failing `assert` is an error that shows the state, and that a
+/// failing `assume` is reported as infeasible.
+#[test]
+fn test_repl_assert_and_assume() {
+    let mut repl = Repl::new();
+
+    repl.handle("x := 3");
+
+    assert_eq!(
+        repl.handle("assert x = 4"),
+        Response::Error("Assertion failed: x = 4\n = note: the state was [x -> 3]".to_string())
+    );
+    assert_eq!(repl.handle("assume x = 4; x := 4"), output("infeasible"));
+    assert_eq!(repl.handle("x"), output("3"));
+}
+
+/// Check that inputs are parsed in the block style that was set.
+#[test]
+fn test_repl_block_style() {
+    let mut repl = Repl::new();
+    repl.set_block_style(BlockStyle::Delimited);
+
+    assert_eq!(
+        repl.handle("x := 0; while x <= 2 do { x := x + 1 }; y := x"),
+        output("[x -> 3, y -> 3]")
+    );
+    assert!(matches!(
+        repl.handle("while x <= 5 do x := x + 1"),
+        Response::Error(_)
+    ));
+}
+
+/// Check that input that gets further as an expression than as a statement
+/// reports what's wrong with it as an expression.
+#[test]
+fn test_repl_expression_error() {
+    let mut repl = Repl::new();
+
+    for input in ["1 +", "x +", "true & "] {
+        let Response::Error(message) = repl.handle(input) else {
+            panic!("Expected an error");
+        };
+
+        assert!(
+            message.starts_with("Expected an expression, found end of input\n"),
+            "{message}"
+        );
+    }
+
+    let Response::Error(message) = repl.handle("skip; 1") else {
+        panic!("Expected an error");
+    };
+
+    assert!(message.starts_with("Expected a statement"), "{message}");
+}
+
+/// Check that lexing errors are annotated the same way as parsing errors.
+#[test]
+fn test_repl_lex_error_annotated() {
+    let mut repl = Repl::new();
+
+    assert_eq!(
+        repl.handle("x := 1 $ 2"),
+        Response::Error("Unknown character `$`\n1 | x := 1 $ 2\n           -".to_string())
+    );
+}
@@ -0,0 +1,79 @@
+use while_tools::lexer::Span;
+use while_tools::utils::{FilePos2d, SourceNavigator};
+
+/// Check that positions are given as a row, a char column and a UTF-16 column.
+#[test]
+fn test_get_position_multibyte() {
+    let source = "skip\n/* 😀σ */ x := 1";
+    let sn = SourceNavigator::new(source);
+
+    let x_index = source.find('x').unwrap();
+
+    assert_eq!(
+        sn.get_position(x_index),
+        FilePos2d {
+            row: 1,
+            col: 9,
+            utf16_col: 10
+        }
+    );
+    assert_eq!(
+        sn.get_position(0),
+        FilePos2d {
+            row: 0,
+            col: 0,
+            utf16_col: 0
+        }
+    );
+}
+
+/// Check that UTF-16 positions convert back into the same index.
+#[test]
+fn test_get_index_from_utf16() {
+    let source = "skip\n/* 😀σ */ x := 1";
+    let sn = SourceNavigator::new(source);
+
+    let x_index = source.find('x').unwrap();
+
+    assert_eq!(sn.get_index_from_utf16(1, 10), x_index);
+    assert_eq!(sn.get_index_from_utf16(0, 2), 2);
+    assert_eq!(sn.get_index_from_utf16(0, 100), 4);
+}
+
+/// Check the conversions between byte and char indices.
+#[test]
+fn test_char_and_byte_indices() {
+    let source = "σ := 😀";
+    let sn = SourceNavigator::new(source);
+
+    assert_eq!(sn.char_index(2), 1);
+    assert_eq!(sn.char_index(source.len()), 6);
+    assert_eq!(sn.byte_index(1), 2);
+    assert_eq!(sn.byte_index(5), 6);
+    assert_eq!(sn.byte_index(100), source.len());
+}
+
+/// Check that lines are returned without their line endings, and without
+/// trimming indentation.
+#[test]
+fn test_get_line() {
+    let sn = SourceNavigator::new("skip\r\n    x := 1\nskip");
+
+    assert_eq!(sn.get_line(0), "skip");
+    assert_eq!(sn.get_line(1), "    x := 1");
+    assert_eq!(sn.get_line(2), "skip");
+}
+
+/// Check that annotations underline the span in chars, not bytes.
+#[test]
+fn test_get_annotated_span_multibyte() {
+    let source = "x := σ − 1";
+    let sn = SourceNavigator::new(source);
+
+    let minus = source.find('−').unwrap();
+
+    assert_eq!(
+        sn.get_annotated_span(Span(minus, minus + '−'.len_utf8())),
+        "1 | x := σ − 1\n           -"
+    );
+}